@@ -0,0 +1,2159 @@
+//! Reading timings in the line format or as JSON, as described in the README.
+//!
+//! [`Events::read`] is the entry point. It returns everything that could be read along with
+//! the errors and warnings found on the way, so that a few malformed lines don't lose the rest
+//! of a trace. [`Events::write_session`] saves what was read so that it opens without parsing
+//! it again.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Timespan {
+    pub start: f64,
+    pub duration: f64,
+}
+
+impl Timespan {
+    pub fn end(&self) -> f64 {
+        self.start + self.duration
+    }
+
+    /// whether any part of the span lies within `[from, to]`, where `None` is unbounded
+    pub fn overlaps(&self, from: Option<f64>, to: Option<f64>) -> bool {
+        from.is_none_or(|from| self.end() >= from) && to.is_none_or(|to| self.start <= to)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Event {
+    pub kind: String,
+    pub id: u32,
+    pub span: Timespan,
+    /// the event never ended, its duration is only a placeholder
+    pub open: bool,
+    /// the event never started, it is drawn from the earliest start. One that ended before
+    /// every start takes no time
+    pub orphan: bool,
+    /// the thread the event ran on, if it was recorded
+    pub thread: Option<u32>,
+    /// a single moment rather than a span, drawn as a marker
+    pub mark: bool,
+    /// the index of the file it was read from in [`Events::sources`]
+    pub source: usize,
+    /// the lines the start and end were read from, numbered like in [`Error::FormatError`]
+    #[serde(default)]
+    pub start_line: Option<usize>,
+    #[serde(default)]
+    pub end_line: Option<usize>,
+    /// why it might have been measured wrongly
+    #[serde(default)]
+    pub suspicion: Option<Suspicion>,
+}
+
+/// something about an event that hints at a mistake in how it was measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Suspicion {
+    /// it ended exactly when it started
+    ZeroDuration,
+    /// it started exactly when the event of the same label with this id ended, which might
+    /// mean that the time in between was counted twice
+    StartsAtEnd(u32),
+}
+
+impl Display for Suspicion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Suspicion::ZeroDuration => write!(f, "took no time"),
+            Suspicion::StartsAtEnd(id) => write!(f, "starts exactly when id {id} ended"),
+        }
+    }
+}
+
+impl Event {
+    /// whether both the start and the end were recorded, so that the duration means something
+    pub fn complete(&self) -> bool {
+        !self.open && !self.orphan && !self.mark
+    }
+
+    /// the lines the event was read from like `12/37`, or a single one if its start and end
+    /// are on the same line or only one of them was recorded
+    pub fn lines(&self) -> Option<String> {
+        match (self.start_line, self.end_line) {
+            (Some(start), Some(end)) if start != end => Some(format!("{start}/{end}")),
+            (Some(line), _) | (None, Some(line)) => Some(line.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// the text shown when hovering the event in the plot, with `offset` added to its start
+    pub fn tooltip(&self, offset: f64, format: TimeFormat) -> String {
+        let thread = match self.thread {
+            Some(thread) => format!("\nthread: {thread}"),
+            None => String::new(),
+        };
+        let lines = match self.lines() {
+            Some(lines) => format!("\nlines: {lines}"),
+            None => String::new(),
+        };
+        // marks end where they start, and the duration already says if there is no end
+        let end = if self.open || self.mark {
+            String::new()
+        } else {
+            format!("\nend: {}", format.format(self.span.end() + offset))
+        };
+        format!(
+            "kind: {}\nid: {}{thread}\nstart: {}\nduration: {}{end}{lines}",
+            self.kind,
+            self.id,
+            if self.orphan {
+                "no start recorded".to_owned()
+            } else {
+                format.format(self.span.start + offset)
+            },
+            if self.open {
+                "no end recorded".to_owned()
+            } else {
+                format.format(self.span.duration)
+            },
+        )
+    }
+}
+
+/// a compiled regex that compares by its source
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern {
+    source: String,
+    regex: regex::Regex,
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            source: source.to_owned(),
+            regex: regex::Regex::new(source)?,
+        })
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::new(&source)
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.source
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ViewBy {
+    Any,
+    Label(String),
+    Labels(HashSet<String>),
+    Regex(Pattern),
+    /// the index of a file in [`Events::sources`]
+    Source(usize),
+}
+
+impl Display for ViewBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViewBy::Any => write!(f, "Any"),
+            ViewBy::Label(x) => write!(f, "Label: {x}"),
+            ViewBy::Labels(xs) => {
+                let mut xs: Vec<_> = xs.iter().map(String::as_str).collect();
+                xs.sort_unstable();
+                write!(f, "Labels: {}", xs.join(", "))
+            }
+            ViewBy::Regex(p) => write!(f, "Regex: {}", p.source),
+            ViewBy::Source(i) => write!(f, "Source: {i}"),
+        }
+    }
+}
+
+impl ViewBy {
+    pub fn matching(&self, other: &Event) -> bool {
+        match self {
+            ViewBy::Any => true,
+            ViewBy::Label(l) => &other.kind == l,
+            ViewBy::Labels(ls) => ls.contains(&other.kind),
+            ViewBy::Regex(p) => p.regex.is_match(&other.kind),
+            ViewBy::Source(i) => other.source == *i,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Events {
+    pub events: Vec<Event>,
+    /// every distinct label, sorted ignoring case
+    pub labels: Vec<String>,
+    /// the latest time at which anything was recorded
+    pub end: f64,
+    /// the absolute time of the earliest start, or of an earlier end without a start. All event
+    /// times are relative to it, so that none are negative
+    pub offset: f64,
+    /// how many lines, or JSON items, were blank, comments or invalid
+    pub skipped: usize,
+    /// the names of the files the events were read from, empty until named with
+    /// [`Events::with_source`]
+    pub sources: Vec<String>,
+    /// the absolute time of the earliest start in each of the [`Events::sources`], which the
+    /// times of their events are relative to
+    #[serde(default)]
+    pub offsets: Vec<f64>,
+    /// the errors and warnings past [`MAX_ERRORS`] that were only counted
+    #[serde(skip)]
+    pub unlisted: Unlisted,
+}
+
+/// how many errors and warnings a single file keeps, so that garbage doesn't fill the memory
+pub const MAX_ERRORS: usize = 100;
+
+/// errors and warnings that were counted, but not kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Unlisted {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// the errors and warnings found while reading a file, up to [`MAX_ERRORS`] of them
+#[derive(Debug, Default)]
+struct Problems {
+    listed: Vec<Error>,
+    unlisted: Unlisted,
+}
+
+impl Problems {
+    fn push(&mut self, error: Error) {
+        if self.listed.len() < MAX_ERRORS {
+            self.listed.push(error);
+        } else if error.is_warning() {
+            self.unlisted.warnings += 1;
+        } else {
+            self.unlisted.errors += 1;
+        }
+    }
+}
+
+/// the version of the sessions written by [`Events::write_session`], raised whenever their
+/// format changes so that older ones can still be told apart
+pub const SESSION_VERSION: u32 = 1;
+
+/// enough of a session to tell it from a single JSON event, and which version it is
+#[derive(serde::Deserialize)]
+struct SessionHeader {
+    jfon_session: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Session<E> {
+    jfon_session: u32,
+    events: E,
+}
+
+/// how [`Events::read`] interprets a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadOptions {
+    /// the duration given to events that never end
+    pub open_duration: f64,
+    /// used by the line format
+    pub separators: Separators,
+    /// labels that only differ in case are the same, and are lowercased
+    pub fold_case: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            open_duration: 1000.0,
+            separators: Separators::default(),
+            fold_case: false,
+        }
+    }
+}
+
+/// reads the line format one line at a time, for files that are still being written
+#[derive(Debug)]
+pub struct LineReader {
+    options: ReadOptions,
+    seqs: Seqs<'static>,
+    /// of the next line
+    line_number: usize,
+    skipped: usize,
+    /// whether the lines are in the compact format, decided by the first one that isn't blank
+    compact: Option<bool>,
+}
+
+impl LineReader {
+    pub fn new(options: ReadOptions) -> Self {
+        Self {
+            options,
+            seqs: Seqs::new(),
+            line_number: 1,
+            skipped: 0,
+            compact: None,
+        }
+    }
+
+    /// reads the next line, without its line ending. Returns what was wrong with it, if
+    /// anything
+    pub fn push_line(&mut self, line: &str) -> Option<Error> {
+        let compact = match self.compact {
+            Some(compact) => compact,
+            None if Events::is_blank(line) => false,
+            None => *self
+                .compact
+                .insert(matches!(Events::parse_compact_line(0, line), Ok(Some(_)))),
+        };
+        let record = if compact {
+            Events::parse_compact_line(self.line_number, line)
+        } else {
+            Events::parse_line(self.line_number, line, self.options.separators)
+        };
+        let record = record.map(|record| record.map(Record::into_owned));
+        let merged = Events::merge(
+            &mut self.seqs,
+            record,
+            self.line_number,
+            self.options.fold_case,
+            &mut self.skipped,
+        );
+        self.line_number += 1;
+        merged.err()
+    }
+
+    /// the events of all lines so far, along with the errors only found when pairing their
+    /// starts and ends. These can change with later lines, unlike those of
+    /// [`LineReader::push_line`]
+    pub fn events(&self) -> (Events, Vec<Error>) {
+        Events::from_seqs(
+            &self.seqs,
+            Problems::default(),
+            self.options.open_duration,
+            self.skipped,
+        )
+    }
+}
+
+/// what separates the label from the fields, and the fields from each other in the line format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Separators {
+    pub label: char,
+    pub field: char,
+}
+
+impl Separators {
+    /// the separators that can be picked, along with how they are shown
+    pub const CHOICES: [(char, &'static str); 6] = [
+        (':', ":"),
+        (',', ","),
+        (';', ";"),
+        ('|', "|"),
+        ('\t', "tab"),
+        (' ', "space"),
+    ];
+
+    pub fn name(c: char) -> &'static str {
+        Self::CHOICES
+            .iter()
+            .find(|&&(choice, _)| choice == c)
+            .map_or("?", |&(_, name)| name)
+    }
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            label: ':',
+            field: ',',
+        }
+    }
+}
+
+/// a single line of the line format
+#[derive(Debug, Clone)]
+struct Record<'a> {
+    label: Cow<'a, str>,
+    seqno: u32,
+    /// either `"start"` or `"end"`
+    action: &'static str,
+    time: f64,
+    thread: Option<u32>,
+    /// the end of a line in the compact format, which has both times
+    end: Option<f64>,
+}
+
+impl Record<'_> {
+    fn into_owned(self) -> Record<'static> {
+        Record {
+            label: Cow::Owned(self.label.into_owned()),
+            seqno: self.seqno,
+            action: self.action,
+            time: self.time,
+            thread: self.thread,
+            end: self.end,
+        }
+    }
+}
+
+/// everything read about a single event. The times come with the line they were read from
+#[derive(Debug, Clone, Copy, Default)]
+struct Seq {
+    start: Option<(f64, usize)>,
+    end: Option<(f64, usize)>,
+    thread: Option<u32>,
+    mark: bool,
+}
+
+impl Seq {
+    /// records the `time` of `action` for the `event` with this label and seqno, a `mark`
+    /// being both the start and the end
+    fn set(
+        &mut self,
+        event: (&str, u32),
+        action: &'static str,
+        time: f64,
+        line_number: usize,
+    ) -> Result<(), Error> {
+        match action {
+            "start" => Events::set_time(&mut self.start, event, action, time, line_number),
+            "end" => Events::set_time(&mut self.end, event, action, time, line_number),
+            _ => {
+                self.mark = true;
+                let start = Events::set_time(&mut self.start, event, action, time, line_number);
+                self.end = self.start;
+                start
+            }
+        }
+    }
+}
+
+/// `label` in lowercase if the case of labels should be ignored
+fn fold(label: Cow<'_, str>, fold_case: bool) -> Cow<'_, str> {
+    if fold_case && label.chars().any(char::is_uppercase) {
+        Cow::Owned(label.to_lowercase())
+    } else {
+        label
+    }
+}
+
+/// FNV-1a, which is much faster than the default hasher for the short keys of
+/// [`Events::from_seqs`], and the same on every run
+pub struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+type BuildFnv = std::hash::BuildHasherDefault<Fnv>;
+
+/// keyed by `(label, seqno)`
+type Seqs<'a> = HashMap<(Cow<'a, str>, u32), Seq>;
+
+/// sorts ignoring case, with ties broken by case so that the order is the same every time
+fn sort_labels(labels: &mut [String]) {
+    labels.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+}
+
+/// times may be integers or decimals, but have to be finite and non-negative
+fn is_valid_time(t: &f64) -> bool {
+    t.is_finite() && *t >= 0.0
+}
+
+/// parses a time with an optional unit suffix, normalized to nanoseconds.
+/// Times without a unit are taken as they are.
+pub fn parse_time(s: &str) -> Option<f64> {
+    try_parse_time(s).ok()
+}
+
+/// why [`try_parse_time`] failed
+enum TimeError {
+    NotANumber,
+    /// negative, or too large to be finite
+    OutOfRange,
+}
+
+fn try_parse_time(s: &str) -> Result<f64, TimeError> {
+    let (number, unit) = TimeUnit::ALL
+        .iter()
+        .filter(|unit| **unit != TimeUnit::Raw)
+        .find_map(|unit| Some((s.strip_suffix(unit.suffix())?, *unit)))
+        .unwrap_or((s, TimeUnit::Raw));
+    let number = number.trim_end();
+    // spelled out rather than the result of overflowing
+    let lowercase = number.to_ascii_lowercase();
+    if lowercase.contains("inf") || lowercase.contains("nan") {
+        return Err(TimeError::NotANumber);
+    }
+    let time = number.parse::<f64>().map_err(|_| TimeError::NotANumber)? * unit.nanos();
+    Some(time)
+        .filter(is_valid_time)
+        .ok_or(TimeError::OutOfRange)
+}
+
+/// the unit times are displayed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TimeUnit {
+    /// whatever unit the file used
+    Raw,
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl TimeUnit {
+    /// longer suffixes first, so that they are matched before `s`
+    pub const ALL: [TimeUnit; 5] = [
+        TimeUnit::Raw,
+        TimeUnit::Ns,
+        TimeUnit::Us,
+        TimeUnit::Ms,
+        TimeUnit::S,
+    ];
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeUnit::Raw => "",
+            TimeUnit::Ns => "ns",
+            TimeUnit::Us => "us",
+            TimeUnit::Ms => "ms",
+            TimeUnit::S => "s",
+        }
+    }
+
+    /// the length of one of this unit in nanoseconds
+    pub fn nanos(self) -> f64 {
+        match self {
+            TimeUnit::Raw | TimeUnit::Ns => 1.0,
+            TimeUnit::Us => 1e3,
+            TimeUnit::Ms => 1e6,
+            TimeUnit::S => 1e9,
+        }
+    }
+
+    /// formats a normalized time in this unit
+    pub fn format(self, time: f64) -> String {
+        self.with_suffix(format_number(time / self.nanos(), 3))
+    }
+
+    /// like [`TimeUnit::format`], but with just enough decimals to tell apart the ticks of an
+    /// axis spanning `range`
+    pub fn format_tick(self, time: f64, range: &std::ops::RangeInclusive<f64>) -> String {
+        let decimals = self.tick_decimals(range);
+        self.with_suffix(format!("{:.*}", decimals, time / self.nanos()))
+    }
+
+    fn tick_decimals(self, range: &std::ops::RangeInclusive<f64>) -> usize {
+        let span = (range.end() - range.start()) / self.nanos();
+        // there are about ten ticks, so they are a tenth of the span apart
+        if span > 0.0 {
+            (1.0 - span.log10()).ceil().clamp(0.0, 12.0) as usize
+        } else {
+            3
+        }
+    }
+
+    fn with_suffix(self, number: String) -> String {
+        match self {
+            TimeUnit::Raw => number,
+            _ => format!("{number} {}", self.suffix()),
+        }
+    }
+}
+
+/// how times are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeFormat {
+    pub unit: TimeUnit,
+    /// how many digits after the point times have, unless they are whole numbers
+    pub decimals: usize,
+}
+
+impl TimeFormat {
+    /// like [`TimeUnit::format`], with [`TimeFormat::decimals`]
+    pub fn format(self, time: f64) -> String {
+        self.unit
+            .with_suffix(format_number(time / self.unit.nanos(), self.decimals))
+    }
+
+    /// like [`TimeUnit::format_tick`], but with no more than [`TimeFormat::decimals`]
+    pub fn format_tick(self, time: f64, range: &std::ops::RangeInclusive<f64>) -> String {
+        let decimals = self.unit.tick_decimals(range).min(self.decimals);
+        self.unit
+            .with_suffix(format!("{:.*}", decimals, time / self.unit.nanos()))
+    }
+}
+
+/// whole numbers without decimals, others with `decimals` of them
+fn format_number(number: f64, decimals: usize) -> String {
+    if number.fract() == 0.0 {
+        format!("{number:.0}")
+    } else {
+        format!("{:.*}", decimals, number)
+    }
+}
+
+impl Display for TimeUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeUnit::Raw => write!(f, "raw"),
+            unit => write!(f, "{}", unit.suffix()),
+        }
+    }
+}
+
+impl Events {
+    /// files with fewer lines are parsed on a single thread, where that is faster
+    #[cfg(not(target_arch = "wasm32"))]
+    const PARALLEL_LINES: usize = 100_000;
+
+    /// like [`Events::read`], but for raw file contents that still need to be decoded, and
+    /// possibly decompressed if they are gzipped
+    pub fn read_bytes(data: &[u8], options: ReadOptions) -> (Self, Vec<Error>) {
+        let decompressed;
+        let data = if data.starts_with(&[0x1f, 0x8b]) {
+            let mut out = Vec::new();
+            let mut decoder = flate2::read::MultiGzDecoder::new(data);
+            if let Err(e) = std::io::Read::read_to_end(&mut decoder, &mut out) {
+                return (Self::default(), vec![Error::DecompressError(e)]);
+            }
+            decompressed = out;
+            &decompressed
+        } else {
+            data
+        };
+        match std::str::from_utf8(data) {
+            Ok(data) => Self::read(data, options),
+            Err(e) => (Self::default(), vec![Error::EncodingError(e)]),
+        }
+    }
+
+    /// parses either the line format or a JSON array of events, depending on the first
+    /// non-whitespace character. Lines like `fetch 3 100 400` are read as the compact format,
+    /// which has the start and end of an event on the same line, and a session written by
+    /// [`Events::write_session`] is read as it was saved.
+    ///
+    /// Malformed lines are skipped and reported along with warnings, all other events are
+    /// still returned.
+    pub fn read(data: &str, options: ReadOptions) -> (Self, Vec<Error>) {
+        match data.trim_start().as_bytes().first() {
+            Some(b'{') => match Self::read_session(data) {
+                Some(session) => session,
+                None => Self::read_json(data, options),
+            },
+            Some(b'[') => Self::read_json(data, options),
+            _ => Self::read_lines(data, options, Self::is_compact(data)),
+        }
+    }
+
+    /// the events of a session, `None` if `data` isn't one
+    fn read_session(data: &str) -> Option<(Self, Vec<Error>)> {
+        let SessionHeader { jfon_session } = serde_json::from_str(data).ok()?;
+        let error = |line_number, kind| {
+            Some((
+                Self::default(),
+                vec![Error::FormatError { line_number, kind }],
+            ))
+        };
+        if jfon_session != SESSION_VERSION {
+            return error(1, FmtErrorKind::UnsupportedVersion(jfon_session));
+        }
+        let mut events = match serde_json::from_str::<Session<Self>>(data) {
+            Ok(session) => session.events,
+            Err(e) => return error(e.line(), FmtErrorKind::JsonError(e)),
+        };
+        // sessions may have been edited by hand, the viewer relies on these to be consistent
+        let sources = events.sources.len().max(1);
+        let mut errors = Vec::new();
+        // counted from 1 like lines
+        let mut index = 1;
+        events.events.retain(|ev| {
+            let valid = ev.source < sources;
+            if !valid {
+                errors.push(Error::FormatError {
+                    line_number: index,
+                    kind: FmtErrorKind::InvalidField("source"),
+                });
+            }
+            index += 1;
+            valid
+        });
+        events.skipped += errors.len();
+        events
+            .events
+            .sort_by(|a, b| a.span.start.total_cmp(&b.span.start));
+        events.update_labels();
+        Some((events, errors))
+    }
+
+    /// saves the events as compact JSON that [`Events::read`] takes back as it is
+    pub fn write_session(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(&Session {
+            jfon_session: SESSION_VERSION,
+            events: self,
+        })
+    }
+
+    /// whether the first line that isn't blank or a comment is in the compact format
+    fn is_compact(data: &str) -> bool {
+        data.lines()
+            .find(|line| !Self::is_blank(line))
+            .is_some_and(|line| matches!(Self::parse_compact_line(0, line), Ok(Some(_))))
+    }
+
+    /// only whitespace, or a comment: a `#` after optional whitespace. A `#` later in the
+    /// line, such as in a label, is kept
+    fn is_blank(line: &str) -> bool {
+        let trimmed = line.trim_start();
+        trimmed.is_empty() || trimmed.starts_with('#')
+    }
+
+    fn read_lines<'a>(data: &'a str, options: ReadOptions, compact: bool) -> (Self, Vec<Error>) {
+        let ReadOptions {
+            open_duration,
+            separators,
+            fold_case,
+        } = options;
+        let lines: Vec<_> = data.lines().collect();
+        let parse = |(i, line): (usize, &&'a str)| {
+            let line_number = i + 1;
+            if compact {
+                Self::parse_compact_line(line_number, line)
+            } else {
+                Self::parse_line(line_number, line, separators)
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let parsed: Vec<_> = if lines.len() >= Self::PARALLEL_LINES {
+            use rayon::prelude::*;
+            lines.par_iter().enumerate().map(parse).collect()
+        } else {
+            lines.iter().enumerate().map(parse).collect()
+        };
+        #[cfg(target_arch = "wasm32")]
+        let parsed: Vec<_> = lines.iter().enumerate().map(parse).collect();
+
+        // merged in order, so that duplicates are reported on their later line
+        let mut seqs = Seqs::new();
+        let mut problems = Problems::default();
+        let mut skipped = 0;
+        for (line_number, record) in (1..).zip(parsed) {
+            if let Err(e) = Self::merge(&mut seqs, record, line_number, fold_case, &mut skipped) {
+                problems.push(e);
+            }
+        }
+
+        Self::from_seqs(&seqs, problems, open_duration, skipped)
+    }
+
+    /// adds a parsed line to `seqs`. Lines that don't add to an event count as `skipped`
+    fn merge<'a>(
+        seqs: &mut Seqs<'a>,
+        record: Result<Option<Record<'a>>, Error>,
+        line_number: usize,
+        fold_case: bool,
+        skipped: &mut usize,
+    ) -> Result<(), Error> {
+        let record = match record {
+            Ok(Some(Record {
+                label,
+                seqno,
+                action,
+                time,
+                thread,
+                end,
+            })) => {
+                // only copied if the label had escapes, for naming duplicates
+                let name = label.clone();
+                let event = (name.as_ref(), seqno);
+                let entry = seqs.entry((fold(label, fold_case), seqno)).or_default();
+                entry.thread = thread.or(entry.thread);
+                let start = entry.set(event, action, time, line_number);
+                match end {
+                    Some(end) => start.and(Self::set_time(
+                        &mut entry.end,
+                        event,
+                        "end",
+                        end,
+                        line_number,
+                    )),
+                    None => start,
+                }
+            }
+            Ok(None) => {
+                *skipped += 1;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &record {
+            *skipped += usize::from(!e.is_warning());
+        }
+        record
+    }
+
+    /// parses a single line, `None` if it is blank or a comment
+    fn parse_line(
+        line_number: usize,
+        line: &str,
+        separators: Separators,
+    ) -> Result<Option<Record<'_>>, Error> {
+        use FmtErrorKind::*;
+        if Self::is_blank(line) {
+            return Ok(None);
+        }
+
+        let syntax_error = |column, expected| Error::syntax(line_number, column, expected);
+        let split = Self::split_label(line, separators.label)
+            .map_err(|column| syntax_error(column, Field::Label))?;
+        if let Some((label, rest)) = split {
+            // yields each field along with the column it starts at
+            let mut column = line.len() - rest.len();
+            let mut parts = rest.split(separators.field).map(|part| {
+                let start = column;
+                column += part.len() + separators.field.len_utf8();
+                // spaces around the separators, or a stray `\r` from Windows
+                let trimmed = part.trim_start();
+                (trimmed.trim_end(), start + part.len() - trimmed.len())
+            });
+            let mut next_field = |expected| {
+                parts
+                    .next()
+                    .ok_or_else(|| syntax_error(line.len(), expected))
+            };
+
+            let (seqno, col) = next_field(Field::Seqno)?;
+            let seqno = seqno
+                .parse()
+                .map_err(Error::from_int(line_number, col, Field::Seqno))?;
+
+            let (action, action_col) = next_field(Field::Action)?;
+
+            let (time, col) = next_field(Field::Time)?;
+            let time = try_parse_time(time).map_err(Error::from_time(line_number, col))?;
+
+            let thread = Self::parse_thread(line_number, parts)?;
+
+            let action = match action {
+                "start" => "start",
+                "end" => "end",
+                "mark" => "mark",
+                x => {
+                    return Err(Error::FormatError {
+                        line_number,
+                        kind: InvalidAction {
+                            column: action_col,
+                            action: x.to_owned(),
+                        },
+                    })
+                }
+            };
+            return Ok(Some(Record {
+                label,
+                seqno,
+                action,
+                time,
+                thread,
+                end: None,
+            }));
+        }
+        Err(Error::FormatError {
+            line_number,
+            kind: MissingSeparator(separators.label),
+        })
+    }
+
+    /// parses a line of the compact format, `label id start end` separated by spaces or commas
+    /// and optionally followed by `thread=<n>`. `None` if it is blank or a comment
+    fn parse_compact_line(line_number: usize, line: &str) -> Result<Option<Record<'_>>, Error> {
+        if Self::is_blank(line) {
+            return Ok(None);
+        }
+
+        let time = |(time, col)| try_parse_time(time).map_err(Error::from_time(line_number, col));
+        let mut parts = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| (part, part.as_ptr() as usize - line.as_ptr() as usize));
+        let mut next_field = |expected| {
+            parts
+                .next()
+                .ok_or_else(|| Error::syntax(line_number, line.len(), expected))
+        };
+
+        let (label, _) = next_field(Field::Label)?;
+        let (seqno, col) = next_field(Field::Seqno)?;
+        let seqno = seqno
+            .parse()
+            .map_err(Error::from_int(line_number, col, Field::Seqno))?;
+        let start = time(next_field(Field::Time)?)?;
+        let end = time(next_field(Field::Time)?)?;
+        let thread = Self::parse_thread(line_number, parts)?;
+
+        Ok(Some(Record {
+            label: Cow::Borrowed(label),
+            seqno,
+            action: "start",
+            time: start,
+            thread,
+            end: Some(end),
+        }))
+    }
+
+    /// parses the optional `thread=<n>` from the `fields` after the time, along with the columns
+    /// they start at. Empty fields, e.g. from a trailing separator, are ignored, anything after
+    /// the thread is an error
+    fn parse_thread<'a>(
+        line_number: usize,
+        fields: impl Iterator<Item = (&'a str, usize)>,
+    ) -> Result<Option<u32>, Error> {
+        let mut fields = fields.filter(|(field, _)| !field.is_empty());
+        let thread = match fields.next() {
+            Some((thread, col)) => Some(
+                thread
+                    .strip_prefix("thread=")
+                    .ok_or_else(|| Error::syntax(line_number, col, Field::Thread))?
+                    .parse::<u32>()
+                    .map_err(Error::from_int(line_number, col, Field::Thread))?,
+            ),
+            None => None,
+        };
+        match fields.next() {
+            Some((_, col)) => Err(Error::syntax(line_number, col, Field::Thread)),
+            None => Ok(thread),
+        }
+    }
+
+    /// splits `line` into its label and the fields after the `separator`, `None` if there is
+    /// no `separator`. A label starting with `"` is quoted, so that it can contain the
+    /// `separator`, and `\"` and `\\` stand for `"` and `\` in it. Whitespace before the quote is
+    /// ignored. Otherwise the label is
+    /// everything up to the first `separator`, without surrounding whitespace. Fails with the
+    /// column of the malformed quote.
+    fn split_label(line: &str, separator: char) -> Result<Option<(Cow<'_, str>, &str)>, usize> {
+        let trimmed = line.trim_start();
+        let Some(quoted) = trimmed.strip_prefix('"') else {
+            return Ok(line
+                .split_once(separator)
+                .map(|(label, rest)| (Cow::Borrowed(label.trim()), rest)));
+        };
+        let mut escaped = false;
+        let close = quoted.char_indices().find(|&(_, c)| {
+            let close = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            close
+        });
+        let Some((close, _)) = close else {
+            return Err(line.len());
+        };
+        let rest = quoted[close + 1..]
+            .strip_prefix(separator)
+            .ok_or(line.len() - trimmed.len() + close + 2)?;
+        let label = &quoted[..close];
+        if !label.contains('\\') {
+            return Ok(Some((Cow::Borrowed(label), rest)));
+        }
+        let mut unquoted = String::with_capacity(label.len());
+        let mut chars = label.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => unquoted.extend(chars.next()),
+                c => unquoted.push(c),
+            }
+        }
+        Ok(Some((Cow::Owned(unquoted), rest)))
+    }
+
+    /// stores `time` in `slot`, with a warning naming the `event` if it was already set
+    fn set_time(
+        slot: &mut Option<(f64, usize)>,
+        (label, seqno): (&str, u32),
+        action: &'static str,
+        time: f64,
+        line_number: usize,
+    ) -> Result<(), Error> {
+        match slot.replace((time, line_number)) {
+            Some((_, first_line)) => Err(Error::FormatError {
+                line_number,
+                kind: FmtErrorKind::Duplicate {
+                    label: label.to_owned(),
+                    seqno,
+                    action,
+                    first_line,
+                },
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// parses a JSON array of objects like `{"kind":"fetch","id":3,"start":100,"end":400}`.
+    /// `end` is optional. The position in the array, from 1, is reported as the line number in
+    /// errors.
+    fn read_json(data: &str, options: ReadOptions) -> (Self, Vec<Error>) {
+        use serde_json::Value;
+
+        let ReadOptions {
+            open_duration,
+            fold_case,
+            ..
+        } = options;
+
+        let items = match serde_json::from_str(data) {
+            Ok(Value::Array(items)) => items,
+            Ok(obj @ Value::Object(_)) => vec![obj],
+            Ok(_) => {
+                let e = Error::FormatError {
+                    line_number: 1,
+                    kind: FmtErrorKind::NotAnArray,
+                };
+                return (Self::default(), vec![e]);
+            }
+            Err(e) => {
+                let e = Error::FormatError {
+                    line_number: e.line(),
+                    kind: FmtErrorKind::JsonError(e),
+                };
+                return (Self::default(), vec![e]);
+            }
+        };
+
+        let mut seqs = Seqs::new();
+        let mut problems = Problems::default();
+        let mut skipped = 0;
+        for (line_number, item) in (1..).zip(&items) {
+            if let Err(e) = Self::read_json_item(&mut seqs, line_number, item, fold_case) {
+                skipped += usize::from(!e.is_warning());
+                problems.push(e);
+            }
+        }
+
+        Self::from_seqs(&seqs, problems, open_duration, skipped)
+    }
+
+    fn read_json_item<'a>(
+        seqs: &mut Seqs<'a>,
+        line_number: usize,
+        item: &'a serde_json::Value,
+        fold_case: bool,
+    ) -> Result<(), Error> {
+        use serde_json::Value;
+        use FmtErrorKind::*;
+
+        let field = |name: &'static str| {
+            item.get(name).ok_or(Error::FormatError {
+                line_number,
+                kind: MissingField(name),
+            })
+        };
+        let invalid = |name: &'static str| Error::FormatError {
+            line_number,
+            kind: InvalidField(name),
+        };
+
+        let label = field("kind")?.as_str().ok_or_else(|| invalid("kind"))?;
+        let seqno = field("id")?
+            .as_u64()
+            .and_then(|id| u32::try_from(id).ok())
+            .ok_or_else(|| invalid("id"))?;
+        let time = |name, value: &Value| {
+            let time = match value {
+                Value::String(s) => parse_time(s),
+                value => value.as_f64().filter(is_valid_time),
+            };
+            time.ok_or_else(|| invalid(name))
+        };
+        let start = time("start", field("start")?)?;
+        let mark = match item.get("mark") {
+            None | Some(Value::Null) => false,
+            Some(mark) => mark.as_bool().ok_or_else(|| invalid("mark"))?,
+        };
+        let end = match item.get("end") {
+            None | Some(Value::Null) => None,
+            Some(end) => Some(time("end", end)?),
+        };
+        let thread = match item.get("thread") {
+            None | Some(Value::Null) => None,
+            Some(thread) => Some(
+                thread
+                    .as_u64()
+                    .and_then(|thread| u32::try_from(thread).ok())
+                    .ok_or_else(|| invalid("thread"))?,
+            ),
+        };
+
+        let entry = seqs
+            .entry((fold(Cow::Borrowed(label), fold_case), seqno))
+            .or_default();
+        entry.thread = thread.or(entry.thread);
+        let event = (label, seqno);
+        if mark {
+            return entry.set(event, "mark", start, line_number);
+        }
+        let start = Self::set_time(&mut entry.start, event, "start", start, line_number);
+        if let Some(end) = end {
+            Self::set_time(&mut entry.end, event, "end", end, line_number)?;
+        }
+        start
+    }
+
+    /// turns the collected `(start, end)` pairs into events relative to the earliest start,
+    /// adding any inconsistent pairs to the `problems` found while reading them
+    fn from_seqs(
+        seqs: &Seqs,
+        mut problems: Problems,
+        open_duration: f64,
+        skipped: usize,
+    ) -> (Self, Vec<Error>) {
+        // an end without a start stands in for one
+        let min = seqs
+            .values()
+            .filter_map(|seq| seq.start.or(seq.end).map(|(time, _)| time))
+            .reduce(f64::min)
+            .unwrap_or(0.0);
+        let max = seqs
+            .values()
+            .flat_map(|seq| [seq.start, seq.end])
+            .filter_map(|time| time.map(|(time, _)| time))
+            .reduce(f64::max)
+            .unwrap_or(0.0);
+
+        // the events of each label by the exact time they ended at, to find those starting then
+        let mut ends = HashMap::with_capacity_and_hasher(seqs.len(), BuildFnv::default());
+        ends.extend(
+            seqs.iter()
+                .filter(|(_, seq)| !seq.mark && seq.start.is_some())
+                .filter_map(|((label, id), seq)| {
+                    let (end, _) = seq.end?;
+                    Some(((label.as_ref(), end.to_bits()), *id))
+                }),
+        );
+
+        let mut events: Vec<_> = seqs
+            .iter()
+            .filter_map(
+                |(
+                    (label, id),
+                    &Seq {
+                        start,
+                        end,
+                        thread,
+                        mark,
+                    },
+                )| {
+                    let suspicion = match (start, end) {
+                        _ if mark => None,
+                        (Some((start, _)), Some((end, _))) if start == end => {
+                            Some(Suspicion::ZeroDuration)
+                        }
+                        (Some((start, _)), _) => ends
+                            .get(&(label.as_ref(), start.to_bits()))
+                            .filter(|&other| other != id)
+                            .map(|&other| Suspicion::StartsAtEnd(other)),
+                        (None, _) => None,
+                    };
+                    let start_line = start.map(|(_, line_number)| line_number);
+                    let end_line = end.map(|(_, line_number)| line_number);
+                    let (start, orphan) = match (start, end) {
+                        (Some((start, _)), _) => (start, false),
+                        (None, Some((_, line_number))) => {
+                            problems.push(Error::FormatError {
+                                line_number,
+                                kind: FmtErrorKind::MissingStart,
+                            });
+                            (min, true)
+                        }
+                        (None, None) => return None,
+                    };
+                    let duration = match end {
+                        Some((end, line_number)) if end < start => {
+                            problems.push(Error::FormatError {
+                                line_number,
+                                kind: FmtErrorKind::EndBeforeStart {
+                                    label: label.to_string(),
+                                    seqno: *id,
+                                    start_line: start_line.unwrap_or(line_number),
+                                },
+                            });
+                            return None;
+                        }
+                        Some((end, _)) => end - start,
+                        None => open_duration,
+                    };
+                    Some(Event {
+                        kind: label.to_string(),
+                        id: *id,
+                        span: Timespan {
+                            start: start - min,
+                            duration,
+                        },
+                        open: end.is_none(),
+                        orphan,
+                        thread,
+                        mark,
+                        source: 0,
+                        start_line,
+                        end_line,
+                        suspicion,
+                    })
+                },
+            )
+            .collect();
+
+        events.sort_by(|a, b| a.span.start.total_cmp(&b.span.start));
+        let Problems {
+            listed: mut errors,
+            unlisted,
+        } = problems;
+        errors.sort_by_key(|e| match e {
+            Error::FormatError { line_number, .. } => *line_number,
+            _ => 0,
+        });
+        // only of the events that were kept, not those left out for ending before they started
+        let mut events = Events {
+            events,
+            labels: Vec::new(),
+            end: (max - min).max(0.0),
+            offset: min,
+            skipped,
+            sources: Vec::new(),
+            offsets: Vec::new(),
+            unlisted,
+        };
+        events.update_labels();
+        (events, errors)
+    }
+
+    /// events that were not read from a file. Their times are taken as they are, relative to
+    /// an offset of zero
+    pub fn from_events(mut events: Vec<Event>) -> Self {
+        events.sort_by(|a, b| a.span.start.total_cmp(&b.span.start));
+        let end = events.iter().map(|ev| ev.span.end()).fold(0.0, f64::max);
+        let mut events = Events {
+            events,
+            end,
+            ..Default::default()
+        };
+        events.update_labels();
+        events
+    }
+
+    /// recomputes [`Events::labels`] after events were added, removed or renamed
+    pub fn update_labels(&mut self) {
+        let labels: HashSet<_> = self.events.iter().map(|ev| ev.kind.as_str()).collect();
+        let mut labels: Vec<_> = labels.into_iter().map(ToOwned::to_owned).collect();
+        sort_labels(&mut labels);
+        self.labels = labels;
+    }
+
+    /// names the file the events were read from
+    pub fn with_source(mut self, name: String) -> Self {
+        // a session already knows the files it was read from
+        if self.sources.is_empty() {
+            self.sources = vec![name];
+            self.offsets = vec![self.offset];
+        }
+        self
+    }
+
+    /// what the times of the events of `source` are relative to. Sessions of older versions
+    /// only know [`Events::offset`]
+    pub fn offset_of(&self, source: usize) -> f64 {
+        self.offsets.get(source).copied().unwrap_or(self.offset)
+    }
+
+    /// adds the events of another file, which become the next sources. Both keep their times
+    /// relative to their own earliest start, so that runs of the same program line up
+    pub fn append(&mut self, other: Events) {
+        let first = self.sources.len();
+        if self.events.is_empty() {
+            self.offset = other.offset;
+        }
+        self.events.extend(other.events.into_iter().map(|mut ev| {
+            ev.source += first;
+            ev
+        }));
+        // stable, so that equal starts stay in the order of their files
+        self.events
+            .sort_by(|a, b| a.span.start.total_cmp(&b.span.start));
+        for label in other.labels {
+            if !self.labels.contains(&label) {
+                self.labels.push(label);
+            }
+        }
+        sort_labels(&mut self.labels);
+        self.end = self.end.max(other.end);
+        self.skipped += other.skipped;
+        self.unlisted.errors += other.unlisted.errors;
+        self.unlisted.warnings += other.unlisted.warnings;
+        self.sources.extend(other.sources);
+        // in case an older session came first
+        self.offsets.resize(first, self.offset);
+        self.offsets.extend(other.offsets);
+    }
+
+    /// changes the placeholder duration of all events that never ended
+    pub fn set_open_duration(&mut self, open_duration: f64) {
+        for ev in self.events.iter_mut().filter(|ev| ev.open) {
+            ev.span.duration = open_duration;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FmtErrorKind {
+    /// `action` was already given for the event with `label` and `seqno` on `first_line`. Only
+    /// a warning, the later one is used
+    Duplicate {
+        label: String,
+        seqno: u32,
+        action: &'static str,
+        first_line: usize,
+    },
+    /// the event with `label` and `seqno` ends before it started on `start_line`. It is left
+    /// out
+    EndBeforeStart {
+        label: String,
+        seqno: u32,
+        start_line: usize,
+    },
+    /// an `end` without a `start`. Only a warning
+    MissingStart,
+    /// the action at `column` is none of `start`, `end` or `mark`
+    InvalidAction {
+        column: usize,
+        action: String,
+    },
+    InvalidField(&'static str),
+    JsonError(serde_json::Error),
+    MissingField(&'static str),
+    /// a line that is neither blank nor a comment lacks the label separator
+    MissingSeparator(char),
+    NotAnArray,
+    /// a session of a version this doesn't know how to read
+    UnsupportedVersion(u32),
+    /// `column` is the byte offset into the line at which `expected` should have been
+    SyntaxError {
+        column: usize,
+        expected: Field,
+    },
+    /// `field` at `column` is a number, but too large or negative
+    OutOfRange {
+        column: usize,
+        field: Field,
+    },
+}
+
+impl FmtErrorKind {
+    /// warnings don't prevent the event from being shown
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            FmtErrorKind::Duplicate { .. } | FmtErrorKind::MissingStart
+        )
+    }
+
+    /// where in the line the error is, if it is about a single field
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            FmtErrorKind::SyntaxError { column, .. }
+            | FmtErrorKind::OutOfRange { column, .. }
+            | FmtErrorKind::InvalidAction { column, .. } => Some(*column),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FmtErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FmtErrorKind::Duplicate {
+                label,
+                seqno,
+                action,
+                first_line,
+            } => write!(
+                f,
+                "`{action}` of `{label}` {seqno} was already given on line {first_line}"
+            ),
+            FmtErrorKind::EndBeforeStart {
+                label,
+                seqno,
+                start_line,
+            } => write!(
+                f,
+                "`end` of `{label}` {seqno} is earlier than its `start` on line {start_line}"
+            ),
+            FmtErrorKind::MissingStart => write!(f, "`end` without a `start`"),
+            FmtErrorKind::InvalidAction { action, .. } => write!(f, "invalid action: `{action}`"),
+            FmtErrorKind::InvalidField(field) => write!(f, "invalid value for field `{field}`"),
+            FmtErrorKind::JsonError(err) => write!(f, "invalid JSON: {err}"),
+            FmtErrorKind::MissingField(field) => write!(f, "missing field `{field}`"),
+            FmtErrorKind::MissingSeparator(separator) => {
+                let separator = Separators::name(*separator);
+                write!(f, "no `{separator}` after the label")
+            }
+            FmtErrorKind::NotAnArray => write!(f, "expected an array of events"),
+            FmtErrorKind::UnsupportedVersion(version) => write!(
+                f,
+                "session version {version} is not supported, only {SESSION_VERSION}"
+            ),
+            FmtErrorKind::SyntaxError { expected, .. } => write!(f, "expected {expected}"),
+            FmtErrorKind::OutOfRange { field, .. } => match field {
+                Field::Time => write!(f, "{field} has to be finite and non-negative"),
+                // rather than the whole `thread=<n>`
+                Field::Thread => write!(f, "thread is larger than {}", u32::MAX),
+                _ => write!(f, "{field} is larger than {}", u32::MAX),
+            },
+        }
+    }
+}
+
+/// the comma separated fields after the label of a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Seqno,
+    Action,
+    Time,
+    /// the optional `thread=<n>` after the time
+    Thread,
+    /// a quoted label has to be closed and followed by the label separator
+    Label,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Seqno => write!(f, "seqno"),
+            Field::Action => write!(f, "action"),
+            Field::Time => write!(f, "time"),
+            Field::Thread => write!(f, "`thread=<n>`"),
+            Field::Label => write!(f, "`\"<label>\"` followed by the label separator"),
+        }
+    }
+}
+
+/// anything that went wrong while reading a file
+#[derive(Debug)]
+pub enum Error {
+    IoError(std::io::Error),
+    EncodingError(std::str::Utf8Error),
+    /// the file looked gzipped, but could not be decompressed
+    DecompressError(std::io::Error),
+    /// the file could not be downloaded
+    HttpError(String),
+    /// `line_number` counts from 1, in JSON the items of the array
+    FormatError {
+        line_number: usize,
+        kind: FmtErrorKind,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::IoError(err) => write!(f, "{err}"),
+            Error::EncodingError(err) => write!(f, "file is not valid UTF-8: {err}"),
+            Error::DecompressError(err) => write!(f, "could not decompress file: {err}"),
+            Error::HttpError(err) => write!(f, "could not download file: {err}"),
+            Error::FormatError { line_number, kind } => {
+                let prefix = if kind.is_warning() {
+                    "Warning"
+                } else {
+                    "Error"
+                };
+                write!(f, "{prefix} on line {line_number}")?;
+                if let Some(column) = kind.column() {
+                    write!(f, ", column {column}")?;
+                }
+                write!(f, ": {kind}")
+            }
+        }
+    }
+}
+
+impl Error {
+    /// warnings don't prevent the event from being shown
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Error::FormatError { kind, .. } if kind.is_warning())
+    }
+
+    fn syntax(line_number: usize, column: usize, expected: Field) -> Self {
+        Error::FormatError {
+            line_number,
+            kind: FmtErrorKind::SyntaxError { column, expected },
+        }
+    }
+
+    fn out_of_range(line_number: usize, column: usize, field: Field) -> Self {
+        Error::FormatError {
+            line_number,
+            kind: FmtErrorKind::OutOfRange { column, field },
+        }
+    }
+
+    /// a number too large for a `u32` isn't a typo
+    fn from_int(
+        line_number: usize,
+        column: usize,
+        field: Field,
+    ) -> impl Fn(std::num::ParseIntError) -> Self {
+        move |e| match e.kind() {
+            std::num::IntErrorKind::PosOverflow => Self::out_of_range(line_number, column, field),
+            _ => Self::syntax(line_number, column, field),
+        }
+    }
+
+    fn from_time(line_number: usize, column: usize) -> impl Fn(TimeError) -> Self {
+        move |e| match e {
+            TimeError::NotANumber => Self::syntax(line_number, column, Field::Time),
+            TimeError::OutOfRange => Self::out_of_range(line_number, column, Field::Time),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &str) -> (Events, Vec<Error>) {
+        Events::read(data, ReadOptions::default())
+    }
+
+    /// the label, id, start and duration of every event, which is what comparing reads needs
+    fn spans(events: &Events) -> Vec<(&str, u32, f64, f64)> {
+        let mut spans: Vec<_> = events
+            .events
+            .iter()
+            .map(|ev| (ev.kind.as_str(), ev.id, ev.span.start, ev.span.duration))
+            .collect();
+        spans.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        spans
+    }
+
+    /// the error of a single malformed `line`
+    fn error_of(line: &str) -> FmtErrorKind {
+        match read(line).1.pop() {
+            Some(Error::FormatError { kind, .. }) => kind,
+            e => panic!("{e:?}"),
+        }
+    }
+
+    #[test]
+    fn session_with_unknown_source() {
+        let (mut events, _) = read("a:1,start,0\na:1,end,5\nb:1,start,1\nb:1,end,2\n");
+        events.events[1].source = 3;
+        events.labels.clear();
+        let session = String::from_utf8(events.write_session().unwrap()).unwrap();
+
+        let (events, errors) = read(&session);
+        assert_eq!(events.events.len(), 1);
+        assert_eq!(events.labels, ["a"]);
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 2,
+                kind: FmtErrorKind::InvalidField("source")
+            }]
+        ));
+    }
+
+    #[test]
+    fn appended_sources_keep_their_offsets() {
+        let (first, _) = read("a:1,start,100\na:1,end,200\n");
+        let (second, _) = read("a:1,start,5000\na:1,end,5100\n");
+        let mut events = first.with_source("first".to_owned());
+        events.append(second.with_source("second".to_owned()));
+        assert_eq!(events.offsets, [100.0, 5000.0]);
+        assert_eq!(
+            events
+                .events
+                .iter()
+                .map(|ev| ev.span.start)
+                .collect::<Vec<_>>(),
+            [0.0, 0.0]
+        );
+
+        let session = String::from_utf8(events.write_session().unwrap()).unwrap();
+        let (session, errors) = read(&session);
+        assert!(errors.is_empty());
+        assert_eq!(session.offset_of(1), 5000.0);
+    }
+
+    #[test]
+    fn bad_lines_are_reported_with_the_valid_events() {
+        let (events, errors) =
+            read("a:1,start,1\nbad\na:1,end,5\nb:x,start,2\nb:1,start,zz\nc:1,foo,3\nc:2,start\nd:1,start,4\nd:1,end,6\n");
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                Error::FormatError { line_number, .. } => *line_number,
+                e => panic!("{e}"),
+            })
+            .collect();
+        assert_eq!(lines, [2, 4, 5, 6, 7]);
+        assert_eq!(events.events.len(), 2);
+        assert_eq!(events.skipped, 5);
+    }
+
+    #[test]
+    fn errors_past_the_cap_are_counted() {
+        let data = "bad\n".repeat(MAX_ERRORS + 50) + "a:1,start,1\na:1,end,2\n";
+        let (events, errors) = read(&data);
+        assert_eq!(errors.len(), MAX_ERRORS);
+        assert_eq!(
+            events.unlisted,
+            Unlisted {
+                errors: 50,
+                warnings: 0
+            }
+        );
+        assert_eq!(events.events.len(), 1);
+    }
+
+    #[test]
+    fn fields_after_the_thread() {
+        let (events, errors) = read("a:1,start,5,\na:1,end,6,thread=1,\n");
+        assert!(errors.is_empty());
+        assert_eq!(events.events[0].thread, Some(1));
+
+        let (_, errors) = read("a:1,start,5,thread=1,zzz\n");
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 1,
+                kind: FmtErrorKind::SyntaxError {
+                    column: 21,
+                    expected: Field::Thread
+                }
+            }]
+        ));
+    }
+
+    #[test]
+    fn quoted_labels_after_whitespace() {
+        let (events, errors) = read("  \"a:b,c\":1,start,5\n\t\"a:b,c\":1,end,6\n");
+        assert!(errors.is_empty());
+        assert_eq!(events.labels, ["a:b,c"]);
+
+        let (_, errors) = read("  \"a:b\"x:1,start,5\n");
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 1,
+                kind: FmtErrorKind::SyntaxError {
+                    column: 7,
+                    expected: Field::Label
+                }
+            }]
+        ));
+    }
+
+    #[test]
+    fn end_before_start_is_an_error() {
+        let (events, errors) = read("foo:1,start,500\nfoo:1,end,100\n");
+        assert!(events.events.is_empty());
+        assert!(matches!(
+            &errors[..],
+            [Error::FormatError {
+                line_number: 2,
+                kind: FmtErrorKind::EndBeforeStart {
+                    label,
+                    seqno: 1,
+                    start_line: 1,
+                },
+            }] if label == "foo"
+        ));
+    }
+
+    #[test]
+    fn fractional_times() {
+        let (events, errors) = read("a:1,start,1.5\na:1,end,3.25\n");
+        assert!(errors.is_empty());
+        assert_eq!(events.offset, 1.5);
+        assert_eq!(events.events[0].span.duration, 1.75);
+
+        // integers and fractions in one trace
+        let (events, errors) =
+            read("a:1,start,100\na:1,end,123.456\nb:1,start,100.5\nb:1,end,101\n");
+        assert!(errors.is_empty());
+        let durations = spans(&events).iter().map(|s| s.3).collect::<Vec<_>>();
+        assert!((durations[0] - 23.456).abs() < 1e-9, "{durations:?}");
+        assert_eq!(durations[1], 0.5);
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let (plain, _) = read("a:1,start,1\nb:1,start,2\na:1,end,3\nb:1,end,5\n");
+        let (commented, errors) = read(
+            "# a run\n\na:1,start,1\n   \n  # indented\nb:1,start,2\n\t\na:1,end,3\n#b:1,end,4\nb:1,end,5\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(spans(&commented), spans(&plain));
+    }
+
+    #[test]
+    fn mixed_units_are_normalized_to_nanoseconds() {
+        let (events, errors) = read(
+            "a:1,start,1ms\na:1,end,1500us\nb:1,start,2000000\nb:1,end,3 ms\nc:1,start,0.001s\nc:1,end,1000000ns\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(events.offset, 1e6);
+        assert_eq!(
+            spans(&events),
+            [("a", 1, 0.0, 5e5), ("b", 1, 1e6, 1e6), ("c", 1, 0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn duplicate_starts_name_both_lines() {
+        let (events, errors) = read("a:1,start,1\na:1,start,2\na:1,end,5\n");
+        assert!(matches!(
+            &errors[..],
+            [Error::FormatError {
+                line_number: 2,
+                kind: FmtErrorKind::Duplicate {
+                    action: "start",
+                    first_line: 1,
+                    ..
+                },
+            }]
+        ));
+        assert!(errors[0].is_warning());
+        assert_eq!(
+            errors[0].to_string(),
+            "Warning on line 2: `start` of `a` 1 was already given on line 1"
+        );
+        assert_eq!(events.events[0].start_line, Some(2));
+    }
+
+    #[test]
+    fn orphan_ends_are_warnings() {
+        let (events, errors) = read("a:1,start,0\na:1,end,1\nb:1,end,5\n");
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 3,
+                kind: FmtErrorKind::MissingStart,
+            }]
+        ));
+        assert!(errors[0].is_warning());
+        let orphan = events.events.iter().find(|ev| ev.kind == "b").unwrap();
+        assert!(orphan.orphan);
+        assert_eq!(orphan.end_line, Some(3));
+        assert_eq!(orphan.span.end(), 5.0);
+    }
+
+    #[test]
+    fn orphan_ends_before_every_start() {
+        let (events, _) = read("a:1,start,100\na:1,end,200\nb:1,end,10\n");
+        assert_eq!(events.offset, 10.0);
+        assert_eq!(spans(&events), [("a", 1, 90.0, 100.0), ("b", 1, 0.0, 0.0)]);
+        assert!(events.events.iter().all(|ev| ev.span.start >= 0.0));
+    }
+
+    #[test]
+    fn quoted_labels_keep_separators_and_escapes() {
+        let (events, errors) = read(
+            "\"db:query\":1,start,1\n\"x,y\":2,start,1\n\"db:query\":1,end,2\n\"a\\\"b\\\\c\":3,mark,4\n",
+        );
+        assert!(errors.is_empty());
+        let mut labels = events.labels.clone();
+        labels.sort();
+        assert_eq!(labels, ["a\"b\\c", "db:query", "x,y"]);
+        let query = events.events.iter().find(|ev| ev.kind == "db:query");
+        assert_eq!(query.unwrap().span.duration, 1.0);
+    }
+
+    #[test]
+    fn tab_separators() {
+        let tabs = Separators {
+            label: '\t',
+            field: '\t',
+        };
+        let options = ReadOptions {
+            separators: tabs,
+            ..ReadOptions::default()
+        };
+        let (events, errors) = Events::read("a\t1\tstart\t1\na\t1\tend\t3\n", options);
+        assert!(errors.is_empty());
+        assert_eq!(spans(&events), [("a", 1, 0.0, 2.0)]);
+
+        let (_, errors) = Events::read("a:1,start,1\n", options);
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                kind: FmtErrorKind::MissingSeparator('\t'),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn lines_without_a_colon_are_errors() {
+        let (events, errors) = read("a:1,start,1\nnothing here\na:1,end,2\n");
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 2,
+                kind: FmtErrorKind::MissingSeparator(':'),
+            }]
+        ));
+        assert_eq!(
+            errors[0].to_string(),
+            "Error on line 2: no `:` after the label"
+        );
+        assert_eq!(events.events.len(), 1);
+        assert_eq!(events.skipped, 1);
+    }
+
+    #[test]
+    fn numbers_out_of_range() {
+        let (_, errors) =
+            read("a:4294967296,start,1\na:1,start,1e400\na:1,start,-1\na:x,start,1\n");
+        let kinds: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                Error::FormatError { kind, .. } => kind,
+                e => panic!("{e}"),
+            })
+            .collect();
+        assert!(matches!(
+            kinds[..],
+            [
+                FmtErrorKind::OutOfRange {
+                    column: 2,
+                    field: Field::Seqno
+                },
+                FmtErrorKind::OutOfRange {
+                    column: 10,
+                    field: Field::Time
+                },
+                FmtErrorKind::OutOfRange {
+                    column: 10,
+                    field: Field::Time
+                },
+                FmtErrorKind::SyntaxError {
+                    column: 2,
+                    expected: Field::Seqno
+                },
+            ]
+        ));
+        assert_eq!(kinds[0].to_string(), "seqno is larger than 4294967295");
+    }
+
+    #[test]
+    fn crlf_and_padded_fields() {
+        let (events, errors) = read("a : 1 , start , 1 \r\n a:1 ,end,3\r\n");
+        assert!(errors.is_empty());
+        assert_eq!(spans(&events), [("a", 1, 0.0, 2.0)]);
+    }
+
+    #[test]
+    fn only_leading_hashes_start_comments() {
+        let (events, errors) =
+            read("  # a:1,start,1\na#b:1,start,1\na#b:1,end,2 # not a comment\n");
+        assert_eq!(events.labels, ["a#b"]);
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 3,
+                kind: FmtErrorKind::SyntaxError {
+                    expected: Field::Time,
+                    ..
+                },
+            }]
+        ));
+    }
+
+    #[test]
+    fn column_of_the_seqno() {
+        assert!(matches!(
+            error_of("a:x,start,1"),
+            FmtErrorKind::SyntaxError {
+                column: 2,
+                expected: Field::Seqno
+            }
+        ));
+    }
+
+    #[test]
+    fn column_of_the_action() {
+        let kind = error_of("a:1, go,1");
+        assert!(matches!(
+            &kind,
+            FmtErrorKind::InvalidAction { column: 5, action } if action == "go"
+        ));
+        assert_eq!(kind.column(), Some(5));
+    }
+
+    #[test]
+    fn column_of_the_time() {
+        assert!(matches!(
+            error_of("a:1,start,zz"),
+            FmtErrorKind::SyntaxError {
+                column: 10,
+                expected: Field::Time
+            }
+        ));
+    }
+
+    #[test]
+    fn column_of_the_thread() {
+        for line in ["a:1,start,1,th=1", "a:1,start,1,thread=x"] {
+            assert!(matches!(
+                error_of(line),
+                FmtErrorKind::SyntaxError {
+                    column: 12,
+                    expected: Field::Thread
+                }
+            ));
+        }
+    }
+
+    #[test]
+    fn column_of_a_missing_field() {
+        // the end of the line, where the field should have been
+        assert!(matches!(
+            error_of("a:1,start"),
+            FmtErrorKind::SyntaxError {
+                column: 9,
+                expected: Field::Time
+            }
+        ));
+    }
+
+    #[test]
+    fn column_of_a_malformed_quote() {
+        assert!(matches!(
+            error_of("\"a:1,start,1"),
+            FmtErrorKind::SyntaxError {
+                column: 12,
+                expected: Field::Label
+            }
+        ));
+        assert!(matches!(
+            error_of("\"a\"b:1,start,1"),
+            FmtErrorKind::SyntaxError {
+                column: 3,
+                expected: Field::Label
+            }
+        ));
+    }
+
+    #[test]
+    fn errors_without_a_column() {
+        assert_eq!(error_of("nothing here").column(), None);
+        let error = &read("a:x,start,1").1[0];
+        assert_eq!(
+            error.to_string(),
+            "Error on line 1, column 2: expected seqno"
+        );
+    }
+
+    #[test]
+    fn duplicate_ends_name_both_lines() {
+        let (events, errors) = read("a:1,start,1\na:1,end,4\na:1,end,5\n");
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 3,
+                kind: FmtErrorKind::Duplicate {
+                    action: "end",
+                    first_line: 2,
+                    ..
+                },
+            }]
+        ));
+        // the later one wins, like for starts
+        assert_eq!(events.events[0].span.duration, 4.0);
+        assert_eq!(events.events[0].end_line, Some(3));
+    }
+
+    #[test]
+    fn end_before_start_keeps_the_other_events() {
+        let (events, errors) = read("a 1 0 5\na 2 10 3\nb 1 2 4\n");
+        assert_eq!(spans(&events), [("a", 1, 0.0, 5.0), ("b", 1, 2.0, 2.0)]);
+
+        // a label whose only event ends before it starts isn't listed
+        let (events, _) = read("a 1 10 3\nb 1 2 4\n");
+        assert_eq!(events.labels, ["b"]);
+        assert_eq!(events.events.len(), 1);
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 2,
+                kind: FmtErrorKind::EndBeforeStart { seqno: 2, .. },
+            }]
+        ));
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_encoding_error() {
+        let (events, errors) =
+            Events::read_bytes(b"a:1,start,1\n\xff\xfe\n", ReadOptions::default());
+        assert!(events.events.is_empty());
+        assert!(matches!(errors[..], [Error::EncodingError(_)]));
+    }
+
+    #[test]
+    fn tooltips_of_open_and_orphan_events() {
+        let (events, _) = read("a:1,start,100\nb:2,start,0\nb:2,end,5\nc:1,end,50\n");
+        let format = TimeFormat {
+            unit: TimeUnit::Raw,
+            decimals: 3,
+        };
+        let tooltip = |kind| {
+            let ev = events.events.iter().find(|ev| ev.kind == kind).unwrap();
+            ev.tooltip(events.offset, format)
+        };
+        assert_eq!(
+            tooltip("a"),
+            "kind: a\nid: 1\nstart: 100\nduration: no end recorded\nlines: 1"
+        );
+        assert_eq!(
+            tooltip("b"),
+            "kind: b\nid: 2\nstart: 0\nduration: 5\nend: 5\nlines: 2/3"
+        );
+        assert_eq!(
+            tooltip("c"),
+            "kind: c\nid: 1\nstart: no start recorded\nduration: 50\nend: 50\nlines: 4"
+        );
+    }
+
+    #[test]
+    fn changing_the_open_duration() {
+        let (mut events, _) = read("a:1,start,0\nb:1,start,10\nb:1,end,30\n");
+        assert_eq!(
+            spans(&events),
+            [("a", 1, 0.0, 1000.0), ("b", 1, 10.0, 20.0)]
+        );
+        events.set_open_duration(50.0);
+        assert_eq!(spans(&events), [("a", 1, 0.0, 50.0), ("b", 1, 10.0, 20.0)]);
+    }
+
+    #[test]
+    fn spans_overlapping_a_window() {
+        let span = Timespan {
+            start: 10.0,
+            duration: 10.0,
+        };
+        // crossing `from`, crossing `to`, and inside
+        assert!(span.overlaps(Some(15.0), Some(30.0)));
+        assert!(span.overlaps(Some(0.0), Some(15.0)));
+        assert!(span.overlaps(Some(0.0), Some(30.0)));
+        assert!(span.overlaps(Some(12.0), Some(18.0)));
+        // outside on either side
+        assert!(!span.overlaps(Some(21.0), Some(30.0)));
+        assert!(!span.overlaps(Some(0.0), Some(9.0)));
+        // no bounds show everything
+        assert!(span.overlaps(None, None));
+        assert!(span.overlaps(None, Some(10.0)));
+        assert!(span.overlaps(Some(20.0), None));
+        assert!(!span.overlaps(Some(20.5), None));
+    }
+
+    #[test]
+    fn spans_touching_a_bound_overlap() {
+        // both bounds are inclusive
+        let span = Timespan {
+            start: 10.0,
+            duration: 10.0,
+        };
+        assert!(span.overlaps(Some(20.0), Some(30.0)));
+        assert!(span.overlaps(Some(0.0), Some(10.0)));
+        let mark = Timespan {
+            start: 10.0,
+            duration: 0.0,
+        };
+        assert!(mark.overlaps(Some(10.0), Some(10.0)));
+    }
+
+    #[test]
+    fn json_items_without_a_start() {
+        let (events, errors) = read(
+            r#"[{"kind":"a","id":1,"start":0,"end":5},{"kind":"a","id":2,"end":7},{"kind":"b","id":1,"start":1}]"#,
+        );
+        assert!(matches!(
+            errors[..],
+            [Error::FormatError {
+                line_number: 2,
+                kind: FmtErrorKind::MissingField("start"),
+            }]
+        ));
+        assert_eq!(events.events.len(), 2);
+        assert_eq!(events.skipped, 1);
+    }
+
+    #[test]
+    fn json_object_or_array() {
+        let (single, errors) = read(r#"{"kind":"a","id":1,"start":10,"end":15}"#);
+        assert!(errors.is_empty());
+        let (array, _) = read(r#"[{"kind":"a","id":1,"start":10,"end":15}]"#);
+        assert_eq!(spans(&single), spans(&array));
+        assert_eq!(spans(&single), [("a", 1, 0.0, 5.0)]);
+
+        // items that aren't objects
+        let (_, errors) = read("[1, 2]\n");
+        assert!(matches!(
+            errors[..],
+            [
+                Error::FormatError {
+                    line_number: 1,
+                    kind: FmtErrorKind::MissingField("kind"),
+                },
+                Error::FormatError { line_number: 2, .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn json_and_lines_give_the_same_durations() {
+        let (lines, _) =
+            read("a:1,start,100\na:1,end,123.5\nb:1,start,110\nb:2,start,120\nb:2,end,200\n");
+        let (json, errors) = read(
+            r#"[
+                {"kind":"a","id":1,"start":100,"end":123.5},
+                {"kind":"b","id":1,"start":110},
+                {"kind":"b","id":2,"start":120,"end":200}
+            ]"#,
+        );
+        assert!(errors.is_empty());
+        assert_eq!(spans(&json), spans(&lines));
+        assert_eq!(json.offset, lines.offset);
+    }
+}
@@ -2,24 +2,48 @@ use std::future::Future;
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use parking_lot::Mutex;
 
 use eframe::egui::{
     self,
-    plot::{BoxElem, BoxPlot, BoxSpread, Legend, Plot},
+    plot::{
+        BoxElem, BoxPlot, BoxSpread, Line, MarkerShape, Plot, PlotBounds, Points, Polygon, VLine,
+    },
     ComboBox,
 };
 use eframe::egui::{Layout, RichText};
-use eframe::epaint::Color32;
+use eframe::epaint::{Color32, Hsva, Stroke};
+
+#[cfg(not(target_arch = "wasm32"))]
+use jfon_viewer::parse_time;
+use jfon_viewer::{
+    Error, Event, Events, Fnv, Pattern, ReadOptions, Separators, TimeFormat, TimeUnit, Unlisted,
+    ViewBy,
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<(), eframe::Error> {
     // Log to stdout (if you run with `RUST_LOG=debug`).
     tracing_subscriber::fmt::init();
 
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{}", Args::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.check {
+        let filename = args.filename.as_deref().unwrap_or_default();
+        std::process::exit(if check(filename) { 0 } else { 1 });
+    }
+
     let options = eframe::NativeOptions {
         // initial_window_size: Some(egui::vec2(320.0, 240.0)),
         ..Default::default()
@@ -27,17 +51,142 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "JFON viewer",
         options,
-        Box::new(|_cc| {
-            let mut analyzer = Analyzer::new();
-            if let Some(filename) = std::env::args().nth(1) {
+        Box::new(move |cc| {
+            let mut analyzer = Analyzer::restore(cc.storage);
+            if let Some(filename) = args.filename {
                 analyzer.filename = filename;
-                analyzer.read();
+                if args.stream {
+                    analyzer.stream(&cc.egui_ctx);
+                } else {
+                    analyzer.read(&cc.egui_ctx);
+                }
+            } else if std::path::Path::new(&analyzer.filename).is_file()
+                || is_url(&analyzer.filename)
+            {
+                analyzer.read(&cc.egui_ctx);
+            }
+            if let Some(label) = args.view_by {
+                let labels = &analyzer.events.lock().labels;
+                // downloads are still running, so their labels can't be checked yet
+                analyzer.view_by = if labels.contains(&label) || is_url(&analyzer.filename) {
+                    ViewBy::Label(label)
+                } else {
+                    log::warn!("there is no label `{label}`, showing all of them");
+                    ViewBy::Any
+                };
+            }
+            if args.from.is_some() {
+                analyzer.from = args.from;
+            }
+            if args.to.is_some() {
+                analyzer.to = args.to;
             }
             Box::new(analyzer)
         }),
     )
 }
 
+/// what was given on the command line
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct Args {
+    filename: Option<String>,
+    view_by: Option<String>,
+    from: Option<f64>,
+    to: Option<f64>,
+    /// read the file line by line while it's being written
+    stream: bool,
+    /// print what was read to stdout instead of opening a window
+    check: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Args {
+    const USAGE: &'static str =
+        "usage: jfon-viewer [--view-by <label>] [--from <time>] [--to <time>] [--stream] [--check] [<file>]";
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("`{name}` needs a value"));
+            let time = |time: String| parse_time(&time).ok_or(format!("invalid time `{time}`"));
+            match arg.as_str() {
+                "-h" | "--help" => {
+                    println!("{}", Self::USAGE);
+                    std::process::exit(0);
+                }
+                "--view-by" => parsed.view_by = Some(value(&arg)?),
+                "--from" => parsed.from = Some(time(value(&arg)?)?),
+                "--to" => parsed.to = Some(time(value(&arg)?)?),
+                "--stream" => parsed.stream = true,
+                "--check" => parsed.check = true,
+                // `-` is stdin
+                flag if flag.starts_with("--") => return Err(format!("unknown flag `{flag}`")),
+                _ if parsed.filename.is_some() => return Err("only one file can be opened".into()),
+                _ => parsed.filename = Some(arg),
+            }
+        }
+        if parsed.stream && parsed.filename.is_none() {
+            return Err("`--stream` needs a file, or `-` for stdin".into());
+        }
+        if parsed.check && parsed.filename.is_none() {
+            return Err("`--check` needs a file, or `-` for stdin".into());
+        }
+        Ok(parsed)
+    }
+}
+
+/// reads `filename` with the default options and prints how many events and labels it has
+/// along with every error and warning as JSON. Returns whether there were no errors
+#[cfg(not(target_arch = "wasm32"))]
+fn check(filename: &str) -> bool {
+    use serde_json::json;
+
+    let data = if filename == "-" {
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut data).map(|_| data)
+    } else if is_url(filename) {
+        download(filename).map_err(std::io::Error::other)
+    } else {
+        std::fs::read(filename)
+    };
+    let dir = std::path::Path::new(filename);
+    let (events, errors) = match data {
+        _ if dir.is_dir() => read_dir(dir, ReadOptions::default()),
+        Ok(data) => Events::read_bytes(&data, ReadOptions::default()),
+        Err(e) => (Events::default(), vec![Error::IoError(e)]),
+    };
+    let reported: Vec<_> = errors
+        .iter()
+        .map(|error| match error {
+            Error::FormatError { line_number, kind } => json!({
+                "severity": if kind.is_warning() { "warning" } else { "error" },
+                "line": line_number,
+                "column": kind.column(),
+                "message": kind.to_string(),
+            }),
+            error => json!({ "severity": "error", "message": error.to_string() }),
+        })
+        .collect();
+    let listed_warnings = errors.iter().filter(|error| error.is_warning()).count();
+    let error_count = errors.len() - listed_warnings + events.unlisted.errors;
+    let report = json!({
+        "file": filename,
+        "events": events.events.len(),
+        "labels": events.labels.len(),
+        "skipped": events.skipped,
+        "errors": error_count,
+        "warnings": listed_warnings + events.unlisted.warnings,
+        "problems": reported,
+    });
+    println!("{report:#}");
+    error_count == 0
+}
+
+/// where the recently opened files are stored
+#[cfg(not(target_arch = "wasm32"))]
+const RECENT_FILES_KEY: &str = "recent_files";
+
 #[cfg(target_arch = "wasm32")]
 fn main() {
     console_error_panic_hook::set_once();
@@ -51,338 +200,3511 @@ fn main() {
         eframe::start_web(
             "main-canvas",
             web_options,
-            Box::new(|_cc| Box::<Analyzer>::default()),
+            Box::new(|cc| Box::new(Analyzer::restore(cc.storage))),
         )
         .await
         .expect("failed to start eframe");
     });
 }
 
-#[derive(Debug)]
-struct Timespan {
-    start: u64,
-    duration: u64,
+/// summary of the durations of some events
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stats {
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    median: f64,
+    /// the first and third quartile
+    q1: f64,
+    q3: f64,
+    p90: f64,
+    p95: f64,
+    p99: f64,
 }
 
-#[derive(Debug)]
-struct Event {
-    kind: String,
-    id: u32,
-    span: Timespan,
+impl Stats {
+    /// `None` if there are no durations
+    fn new(mut durations: Vec<f64>) -> Option<Self> {
+        durations.sort_by(f64::total_cmp);
+        let count = durations.len();
+        let (&min, &max) = (durations.first()?, durations.last()?);
+        // both indices are the same for an odd count
+        let median = (durations[(count - 1) / 2] + durations[count / 2]) / 2.0;
+        // the smallest duration that at least `p` percent of the durations are at most
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * count as f64).ceil() as usize;
+            durations[rank.clamp(1, count) - 1]
+        };
+        Some(Self {
+            count,
+            min,
+            max,
+            mean: durations.iter().sum::<f64>() / count as f64,
+            median,
+            q1: percentile(25.0),
+            q3: percentile(75.0),
+            p90: percentile(90.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        })
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum ViewBy {
-    Any,
-    Label(String),
+/// the statistics of the visible events of a label
+#[derive(Debug, Clone)]
+struct LabelStats {
+    label: String,
+    count: usize,
+    open: usize,
+    /// `None` if no event of the label ended
+    durations: Option<Stats>,
 }
 
-impl Display for ViewBy {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ViewBy::Any => write!(f, "Any"),
-            ViewBy::Label(x) => write!(f, "Label: {x}"),
+impl LabelStats {
+    fn new(label: String, visible: &[&Event]) -> Self {
+        // open events have no real duration
+        let durations = visible
+            .iter()
+            .filter(|ev| ev.complete())
+            .map(|ev| ev.span.duration)
+            .collect();
+        LabelStats {
+            label,
+            count: visible.len(),
+            open: visible.iter().filter(|ev| ev.open).count(),
+            durations: Stats::new(durations),
         }
     }
 }
 
-impl ViewBy {
-    fn matching(&self, other: &Event) -> bool {
+/// the [`LabelStats`] of every label, along with what they were computed from
+#[derive(Debug)]
+struct StatsCache {
+    events: std::sync::Weak<Events>,
+    view_by: ViewBy,
+    id: Option<u32>,
+    from: Option<f64>,
+    to: Option<f64>,
+    min_duration: Option<f64>,
+    max_duration: Option<f64>,
+    show_orphans: bool,
+    hidden_labels: HashSet<String>,
+    labels: Vec<LabelStats>,
+    /// all visible events together, under the label `all`
+    total: LabelStats,
+    /// how many distinct ids the visible events have
+    ids: usize,
+    /// from the earliest start to the latest end of the visible events
+    span: Option<(f64, f64)>,
+    /// see [`concurrency`]
+    concurrency: Vec<(f64, usize)>,
+    /// see [`idle_gaps`]
+    gaps: Vec<(f64, f64)>,
+}
+
+/// how labels are colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Palette {
+    /// any hue, so that many labels still look different
+    Hues,
+    /// a few colors that are easy to tell apart, shared once there are more labels
+    Categorical,
+    Pastel,
+}
+
+impl Palette {
+    const ALL: [Palette; 3] = [Palette::Hues, Palette::Categorical, Palette::Pastel];
+
+    /// Tableau 10
+    const CATEGORICAL: [Color32; 10] = [
+        Color32::from_rgb(0x4e, 0x79, 0xa7),
+        Color32::from_rgb(0xf2, 0x8e, 0x2b),
+        Color32::from_rgb(0xe1, 0x57, 0x59),
+        Color32::from_rgb(0x76, 0xb7, 0xb2),
+        Color32::from_rgb(0x59, 0xa1, 0x4f),
+        Color32::from_rgb(0xed, 0xc9, 0x48),
+        Color32::from_rgb(0xb0, 0x7a, 0xa1),
+        Color32::from_rgb(0xff, 0x9d, 0xa7),
+        Color32::from_rgb(0x9c, 0x75, 0x5f),
+        Color32::from_rgb(0xba, 0xb0, 0xac),
+    ];
+
+    /// a color derived from the FNV-1a hash of `label`, so that it is the same on every frame
+    /// and every run
+    fn color(self, label: &str) -> Color32 {
+        use std::hash::Hasher;
+        let mut hasher = Fnv::default();
+        hasher.write(label.as_bytes());
+        let hash = hasher.finish();
+        let hue = (hash % 1024) as f32 / 1024.0;
         match self {
-            ViewBy::Any => true,
-            ViewBy::Label(l) => &other.kind == l,
+            Palette::Hues => Hsva::new(hue, 0.85, 0.5, 1.0).into(),
+            Palette::Categorical => Self::CATEGORICAL[(hash % 10) as usize],
+            Palette::Pastel => Hsva::new(hue, 0.4, 0.85, 1.0).into(),
         }
     }
 }
 
-#[derive(Debug, Default)]
-struct Events {
-    events: Vec<Event>,
-    labels: Vec<String>,
-}
-
-impl Events {
-    fn read(data: &str) -> Result<Self, Error> {
-        let mut seqs = HashMap::<_, (Option<u64>, Option<u64>)>::new();
-        let mut labels = HashSet::new();
-        for (line_number, line) in data.lines().enumerate() {
-            use FmtErrorKind::*;
-            if let Some((label, rest)) = line.split_once(':') {
-                labels.insert(label);
-                let mut parts = rest.split(',');
-                let seqno = parts
-                    .next()
-                    .ok_or(Error::FormatError {
-                        line_number,
-                        kind: SyntaxError,
-                    })?
-                    .parse::<u32>()
-                    .map_err(|_| Error::FormatError {
-                        line_number,
-                        kind: SyntaxError,
-                    })?;
-
-                let action = parts.next().ok_or(Error::FormatError {
-                    line_number,
-                    kind: SyntaxError,
-                })?;
-
-                let time = parts
-                    .next()
-                    .ok_or(Error::FormatError {
-                        line_number,
-                        kind: SyntaxError,
-                    })?
-                    .parse::<u64>()
-                    .map_err(|_| Error::FormatError {
-                        line_number,
-                        kind: SyntaxError,
-                    })?;
-
-                let entry = seqs.entry((label, seqno)).or_default();
-                match action {
-                    "start" => entry.0 = Some(time),
-                    "end" => entry.1 = Some(time),
-                    x => {
-                        return Err(Error::FormatError {
-                            line_number,
-                            kind: InvalidAction(x.to_owned()),
-                        })
-                    }
-                }
-            }
+impl Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Palette::Hues => write!(f, "hues"),
+            Palette::Categorical => write!(f, "categorical"),
+            Palette::Pastel => write!(f, "pastel"),
         }
-
-        let min = *seqs
-            .iter()
-            .filter_map(|(&_, (start, _))| start.as_ref())
-            .min()
-            .unwrap_or(&0);
-
-        let mut events: Vec<_> = seqs
-            .iter()
-            .filter_map(|(key, &(start, end))| start.map(|start| (key, (start, end))))
-            .map(|(&(label, id), (start, end))| {
-                let duration = end.map(|e| e - start).unwrap_or(1000);
-                Event {
-                    kind: label.to_string(),
-                    id,
-                    span: Timespan {
-                        start: start - min,
-                        duration,
-                    },
-                }
-            })
-            .collect();
-
-        events.sort_by(|a, b| a.span.start.cmp(&b.span.start));
-        Ok(Events {
-            events,
-            labels: labels.iter().copied().map(ToOwned::to_owned).collect(),
-        })
     }
 }
 
+/// the file read last, shown in the status bar
 #[derive(Debug)]
+struct LastRead {
+    name: String,
+    events: usize,
+    /// how long parsing took, which isn't measured on the web
+    took: Option<std::time::Duration>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 struct Analyzer {
     #[cfg(not(target_arch = "wasm32"))]
     filename: String,
-    events: Arc<Mutex<Events>>,
-    error: Arc<Mutex<Option<Error>>>,
+    /// where the web version downloads events from
+    #[cfg(target_arch = "wasm32")]
+    url: String,
+    /// replaced as a whole whenever a file is loaded, so that each frame can draw from a
+    /// snapshot without holding the lock
+    #[serde(skip)]
+    events: Arc<Mutex<Arc<Events>>>,
+    #[serde(skip)]
+    errors: Arc<Mutex<Vec<Error>>>,
+    /// a file is being read or downloaded in the background
+    #[serde(skip)]
+    loading: Arc<AtomicBool>,
+    /// how often the events were replaced, so that a [`Analyzer::stream`] notices once it was
+    #[serde(skip)]
+    loads: Arc<AtomicUsize>,
+    #[serde(skip)]
+    last_read: Arc<Mutex<Option<LastRead>>>,
+    /// the file, directory or URL all of the events were read from, which a change of the
+    /// format reads again. `None` once they were picked, dropped or added to
+    #[serde(skip)]
+    loaded_from: Arc<Mutex<Option<String>>>,
     view_by: ViewBy,
+    regex: String,
+    #[serde(skip)]
+    regex_error: Option<regex::Error>,
+    /// events whose label contains this are highlighted, ignoring case
+    #[serde(skip)]
+    search: String,
+    /// only events with this id are shown, once it is a number
+    #[serde(skip)]
+    id_filter: String,
+    /// [`Analyzer::id_filter`] parsed
+    #[serde(skip)]
+    id: Option<u32>,
+    view: View,
+    /// how many buckets the histogram has
+    bins: usize,
+    rows: Rows,
+    row_order: RowOrder,
+    open_spans: OpenSpans,
+    /// draw events that only have an `end`
+    show_orphans: bool,
+    /// labels turned off in the legend of the timeline
+    hidden_labels: HashSet<String>,
+    /// the duration given to events that never ended
+    open_duration: f64,
+    /// used when reading the line format
+    separators: Separators,
+    /// labels that only differ in case are the same
+    fold_case: bool,
+    /// only events overlapping this time window are shown
+    #[serde(skip)]
+    from: Option<f64>,
+    #[serde(skip)]
+    to: Option<f64>,
+    /// only complete events lasting at least, or at most, this long are shown
+    #[serde(skip)]
+    min_duration: Option<f64>,
+    #[serde(skip)]
+    max_duration: Option<f64>,
+    /// show times as they were recorded instead of relative to the earliest start
+    absolute: bool,
+    unit: TimeUnit,
+    /// how many digits after the point shown and exported times have
+    decimals: usize,
+    /// where the plot was drawn in the last frame, in physical pixels
+    #[serde(skip)]
+    plot_rect: Option<egui::Rect>,
+    /// what the timeline showed in the last frame
+    #[serde(skip)]
+    viewport: Option<PlotBounds>,
+    /// the `(kind, id)` of the event that was clicked
+    #[serde(skip)]
+    selected: Option<(String, u32)>,
+    /// move the plot to show all visible events in the next frame
+    #[serde(skip)]
+    fit: bool,
+    /// move the plot to these bounds in the next frame
+    #[serde(skip)]
+    zoom_to: Option<PlotBounds>,
+    /// the event that was last right-clicked, along with bounds that show it
+    #[serde(skip)]
+    context_event: Option<((String, u32), PlotBounds)>,
+    /// show the statistics panel
+    show_stats: bool,
+    /// list what might be wrong with the events, and outline the suspicious ones
+    show_health: bool,
+    /// draw how many events are active over time
+    show_concurrency: bool,
+    /// shade the longest [`idle_gaps`] on the timeline
+    show_gaps: bool,
+    /// draw bars at least [`MIN_BAR_PIXELS`] wide, so that very short events don't vanish
+    min_bar_width: bool,
+    palette: Palette,
+    /// list every event active at the hovered time instead of describing the hovered bar
+    crosshair: bool,
+    /// draw the events of a label that follow each other this closely as a single bar
+    merge_gap: Option<f64>,
+    /// how much of its row a bar fills, the rest is space between the rows
+    bar_height: f64,
+    /// at most this many bars are drawn, the earliest of those in view first. 0 draws all
+    max_bars: usize,
+    /// how many times longer a unit of time is drawn than a row is high
+    aspect: f32,
+    /// stretch the timeline to fill the plot instead of keeping [`Analyzer::aspect`]
+    auto_aspect: bool,
+    /// during playback, events that start after this are dimmed
+    #[serde(skip)]
+    cursor: Option<f64>,
+    /// the cursor moves on every frame
+    #[serde(skip)]
+    playing: bool,
+    /// how many seconds the cursor takes to sweep across all shown events
+    play_seconds: f64,
+    /// use the dark theme rather than the light one
+    dark_mode: bool,
+    #[serde(skip)]
+    stats_cache: Option<StatsCache>,
+    /// the events last loaded along with the [`egui::InputState::time`] they were loaded at
+    #[serde(skip)]
+    loaded: Option<(std::sync::Weak<Events>, f64)>,
+    /// capture the plot as a PNG after the next frame was rendered
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    export_png: bool,
+    /// reload the file whenever it changes on disk
+    #[cfg(not(target_arch = "wasm32"))]
+    watch: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    watcher: Option<Watcher>,
+    /// recently opened files, most recent first. Stored separately, since they are shared
+    /// with the file dialog
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    recent: Arc<Mutex<Vec<String>>>,
+    /// files larger than this many MiB are only read once confirmed, 0 for no limit
+    #[cfg(not(target_arch = "wasm32"))]
+    max_file_mib: u64,
+    /// shared with the file dialog
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    too_large: Arc<Mutex<Option<TooLarge>>>,
 }
 
+/// a file over [`Analyzer::max_file_mib`] that waits for the user to decide how to open it
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug)]
-pub enum FmtErrorKind {
-    InvalidAction(String),
-    SyntaxError,
+struct TooLarge {
+    path: String,
+    size: u64,
+    /// see [`Analyzer::loader`]
+    append: bool,
+}
+
+/// the size of `path` if it is over `limit_mib`
+#[cfg(not(target_arch = "wasm32"))]
+fn too_large(path: &std::path::Path, limit_mib: u64) -> Option<u64> {
+    let size = std::fs::metadata(path).ok()?.len();
+    (limit_mib > 0 && size > limit_mib << 20).then_some(size)
 }
 
+/// watches a file for changes
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug)]
-pub enum Error {
-    IoError(std::io::Error),
-    FormatError {
-        line_number: usize,
-        kind: FmtErrorKind,
-    },
+struct Watcher {
+    filename: String,
+    _watcher: notify::RecommendedWatcher,
+    changes: std::sync::mpsc::Receiver<()>,
+    /// when the last change arrived that was not yet reloaded
+    changed_at: Option<std::time::Instant>,
 }
-impl Error {
-    fn show(&self, ui: &mut egui::Ui) {
-        const COLOR: Color32 = Color32::from_rgb(200, 0, 0);
-        match self {
-            Error::IoError(err) => {
-                ui.colored_label(COLOR, format!("{err}"));
-            }
-            Error::FormatError { line_number, kind } => {
-                ui.horizontal(|ui| {
-                    ui.label(RichText::new("Error on Line").color(COLOR));
-                    ui.label(
-                        RichText::new(format!("{line_number}"))
-                            .color(COLOR)
-                            .monospace(),
-                    );
-                    ui.label(RichText::new(":").color(COLOR));
-                    match kind {
-                        FmtErrorKind::InvalidAction(action) => {
-                            ui.label(
-                                RichText::new(format!("invalid action: `{action}`")).color(COLOR),
-                            );
-                        }
-                        FmtErrorKind::SyntaxError => {
-                            ui.label(RichText::new("Syntax Error!").color(COLOR));
-                        }
-                    }
-                });
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Watcher {
+    /// how long a file has to stay unchanged before it is reloaded
+    const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(200);
+
+    fn new(filename: &str, ctx: &egui::Context) -> notify::Result<Self> {
+        use notify::Watcher as _;
+
+        let (tx, changes) = std::sync::mpsc::channel();
+        let ctx = ctx.clone();
+        let mut watcher = notify::recommended_watcher(move |ev: notify::Result<notify::Event>| {
+            if ev.is_ok_and(|ev| ev.kind.is_modify() || ev.kind.is_create()) {
+                let _ = tx.send(());
+                ctx.request_repaint();
             }
-        }
+        })?;
+        watcher.watch(filename.as_ref(), notify::RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            filename: filename.to_owned(),
+            _watcher: watcher,
+            changes,
+            changed_at: None,
+        })
     }
-}
 
-impl Analyzer {
-    #[cfg(not(target_arch = "wasm32"))]
-    fn read(&mut self) {
-        let c = match std::fs::read_to_string(&self.filename) {
-            Ok(c) => c,
-            Err(e) => {
-                *self.error.lock() = Some(Error::IoError(e));
-                return;
-            }
+    /// whether the file changed and then stayed unchanged for `DEBOUNCE`
+    fn settled(&mut self, ctx: &egui::Context) -> bool {
+        if self.changes.try_iter().count() > 0 {
+            self.changed_at = Some(std::time::Instant::now());
+        }
+        let Some(changed_at) = self.changed_at else {
+            return false;
         };
-        match Events::read(&c) {
-            Ok(events) => {
-                self.events = Arc::new(Mutex::new(events));
-            }
-            Err(e) => *self.error.lock() = Some(e),
+        let elapsed = changed_at.elapsed();
+        if elapsed < Self::DEBOUNCE {
+            ctx.request_repaint_after(Self::DEBOUNCE - elapsed);
+            return false;
         }
+        self.changed_at = None;
+        true
     }
+}
 
-    fn new() -> Self {
-        Self {
-            #[cfg(not(target_arch = "wasm32"))]
-            filename: String::new(),
-            events: Arc::new(Mutex::new(Events::default())),
-            view_by: ViewBy::Any,
-            error: Arc::new(Mutex::new(None)),
+/// what the plot shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum View {
+    Timeline,
+    /// the distribution of durations
+    Histogram,
+    /// the distribution of durations per label
+    BoxPlot,
+}
+
+impl Display for View {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            View::Timeline => write!(f, "timeline"),
+            View::Histogram => write!(f, "histogram"),
+            View::BoxPlot => write!(f, "box plot"),
         }
     }
 }
 
-impl Default for Analyzer {
-    fn default() -> Self {
-        Self::new()
+/// what the rows of the plot correspond to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Rows {
+    /// one row per seqno
+    Id,
+    /// one row per thread, falling back to the seqno for events without one
+    Thread,
+    /// one row per label
+    Label,
+    /// overlapping events are stacked into lanes, grouped by label
+    Lanes,
+    /// one row per label, showing when any of its events was active instead of the events
+    Union,
+}
+
+impl Rows {
+    const ALL: [Rows; 5] = [
+        Rows::Id,
+        Rows::Thread,
+        Rows::Label,
+        Rows::Lanes,
+        Rows::Union,
+    ];
+}
+
+impl Display for Rows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rows::Id => write!(f, "id"),
+            Rows::Thread => write!(f, "thread"),
+            Rows::Label => write!(f, "label"),
+            Rows::Lanes => write!(f, "lanes"),
+            Rows::Union => write!(f, "union"),
+        }
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-fn execute<F: Future<Output = ()> + Send + 'static>(f: F) {
-    // this is stupid... use any executor of your choice instead
+/// the order of the rows of the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum RowOrder {
+    /// by id or thread, or by label ignoring case
+    Sorted,
+    /// in the order the rows first have an event, without gaps
+    FirstSeen,
+}
 
-    std::thread::spawn(move || futures::executor::block_on(f));
+impl Display for RowOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowOrder::Sorted => write!(f, "sorted"),
+            RowOrder::FirstSeen => write!(f, "first seen"),
+        }
+    }
 }
-#[cfg(target_arch = "wasm32")]
-fn execute<F: Future<Output = ()> + 'static>(f: F) {
-    wasm_bindgen_futures::spawn_local(f);
+
+/// how events that never ended are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum OpenSpans {
+    FixedDuration,
+    ExtendToEnd,
+    /// last until the current time, for traces that are still being written. The latest
+    /// recorded time is taken to be when the file was loaded, and times to be in nanoseconds
+    GrowToNow,
+    Hide,
 }
 
-impl eframe::App for Analyzer {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("JFON viewer");
-            #[cfg(not(target_arch = "wasm32"))]
-            ui.horizontal(|ui| {
-                use eframe::egui::Key;
-                let name_label = ui.label("filename: ");
-                let input = ui
-                    .text_edit_singleline(&mut self.filename)
-                    .labelled_by(name_label.id);
-                if input.lost_focus() && input.ctx.input(|r| r.key_down(Key::Enter)) {
-                    self.read()
-                }
+impl Display for OpenSpans {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenSpans::FixedDuration => write!(f, "fixed duration"),
+            OpenSpans::ExtendToEnd => write!(f, "extend to end"),
+            OpenSpans::GrowToNow => write!(f, "grow to now"),
+            OpenSpans::Hide => write!(f, "hide"),
+        }
+    }
+}
+
+const SELECTED_COLOR: Color32 = Color32::GOLD;
+
+/// see [`Analyzer::min_bar_width`]
+const MIN_BAR_PIXELS: f32 = 2.0;
+
+/// how many of the events under the crosshair are listed
+const CROSSHAIR_EVENTS: usize = 20;
+
+/// how many events [`Analyzer::show_health`] lists before summarizing the rest
+const MAX_SUSPICIOUS_SHOWN: usize = 100;
+
+/// how many of the [`idle_gaps`] are listed and shaded
+const LONGEST_GAPS: usize = 5;
+
+impl Analyzer {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read(&mut self, ctx: &egui::Context) {
+        if self
+            .watcher
+            .as_ref()
+            .is_some_and(|w| w.filename != self.filename)
+        {
+            self.watcher = None;
+        }
+        if self.filename != "-" && !is_url(&self.filename) {
+            let path = std::path::Path::new(&self.filename);
+            if let Some(size) = too_large(path, self.max_file_mib) {
+                *self.too_large.lock() = Some(TooLarge {
+                    path: self.filename.clone(),
+                    size,
+                    append: false,
+                });
+                return;
+            }
+        }
+        self.loads.fetch_add(1, Ordering::Relaxed);
+        if is_url(&self.filename) {
+            self.fetch_url(&self.filename, ctx);
+            return;
+        }
+        let dir = std::path::Path::new(&self.filename);
+        if dir.is_dir() {
+            remember_file(&self.recent, dir);
+            let started = std::time::Instant::now();
+            let (events, errors) = read_dir(dir, self.read_options());
+            *self.last_read.lock() = Some(LastRead {
+                name: self.filename.clone(),
+                events: events.events.len(),
+                took: Some(started.elapsed()),
             });
+            *self.events.lock() = Arc::new(events);
+            *self.errors.lock() = errors;
+            *self.loaded_from.lock() = Some(self.filename.clone());
+            return;
+        }
+        let data = if self.filename == "-" {
+            let mut data = Vec::new();
+            std::io::Read::read_to_end(&mut std::io::stdin(), &mut data).map(|_| data)
+        } else {
+            std::fs::read(&self.filename)
+        };
+        let (events, errors) = match data {
+            Ok(c) => {
+                let source = if self.filename == "-" {
+                    "standard input".to_owned()
+                } else {
+                    remember_file(&self.recent, self.filename.as_ref());
+                    self.filename.clone()
+                };
+                let started = std::time::Instant::now();
+                let (events, errors) = Events::read_bytes(&c, self.read_options());
+                *self.last_read.lock() = Some(LastRead {
+                    name: source.clone(),
+                    events: events.events.len(),
+                    took: Some(started.elapsed()),
+                });
+                (events.with_source(source), errors)
+            }
+            Err(e) => (Events::default(), vec![Error::IoError(e)]),
+        };
+        *self.events.lock() = Arc::new(events);
+        *self.errors.lock() = errors;
+        *self.loaded_from.lock() = (self.filename != "-").then(|| self.filename.clone());
+    }
 
-            ui.horizontal(|ui| {
+    /// lets the user choose a file and loads it in the background, see [`Analyzer::loader`]
+    fn pick_file(&self, ctx: &egui::Context, append: bool) {
+        let task = rfd::AsyncFileDialog::new()
+            .add_filter("jfon", &["jfon", "json", "gz"])
+            .pick_file();
+        let loading = Arc::clone(&self.loading);
+        let done = self.loader(ctx, append, None);
+        #[cfg(not(target_arch = "wasm32"))]
+        let (recent, pending, limit, ctx) = (
+            Arc::clone(&self.recent),
+            Arc::clone(&self.too_large),
+            self.max_file_mib,
+            ctx.clone(),
+        );
+        execute(async move {
+            let file = task.await;
+            if let Some(file) = file {
                 #[cfg(not(target_arch = "wasm32"))]
                 {
-                    if ui.button("reload").clicked() {
-                        self.read();
+                    log::info!("loading {:?}", file.path());
+                    remember_file(&recent, file.path());
+                    if let Some(size) = too_large(file.path(), limit) {
+                        *pending.lock() = Some(TooLarge {
+                            path: file.path().display().to_string(),
+                            size,
+                            append,
+                        });
+                        ctx.request_repaint();
+                        return;
                     }
                 }
-                if ui.button("Open file…").clicked() {
-                    let task = rfd::AsyncFileDialog::new()
-                        .add_filter("jfon", &["jfon"])
-                        .pick_file();
-                    let events = Arc::clone(&self.events);
-                    let error = Arc::clone(&self.error);
-                    execute(async move {
-                        let file = task.await;
-                        if let Some(file) = file {
-                            #[cfg(not(target_arch = "wasm32"))]
-                            log::info!("loading {:?}", file.path());
-
-                            let data = file.read().await;
-                            match Events::read(String::from_utf8(data).unwrap().as_str()) {
-                                Ok(e) => {
-                                    *events.lock() = e;
-                                }
-                                Err(e) => *error.lock() = Some(e),
-                            }
-                        }
-                    });
-                }
-            });
 
-            ui.horizontal(|ui| {
-                ui.label("View By: ");
-                ComboBox::from_label("")
-                    .selected_text(self.view_by.to_string())
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut self.view_by, ViewBy::Any, "Any");
+                loading.store(true, Ordering::Relaxed);
+                done(file.file_name(), Ok(file.read().await));
+            }
+        });
+    }
 
-                        for label in &self.events.lock().labels {
-                            let val = ViewBy::Label(label.clone());
-                            let s = val.to_string();
-                            ui.selectable_value(&mut self.view_by, val, s);
-                        }
-                    });
+    fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            open_duration: self.open_duration,
+            separators: self.separators,
+            fold_case: self.fold_case,
+        }
+    }
 
-                ui.with_layout(Layout::right_to_left(eframe::emath::Align::Max), |ui| {
-                    ui.hyperlink_to(
-                        "github.com/HSMF/jfon-viewer",
-                        "https://github.com/HSMF/jfon-viewer",
-                    )
-                });
+    /// downloads `url` in the background and loads the events in it
+    fn fetch_url(&self, url: &str, ctx: &egui::Context) {
+        log::info!("downloading {url}");
+        self.loading.store(true, Ordering::Relaxed);
+        let done = self.loader(ctx, false, Some(url.to_owned()));
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let url = url.to_owned();
+            std::thread::spawn(move || {
+                let data = download(&url).map_err(Error::HttpError);
+                done(url, data)
             });
+        }
+        #[cfg(target_arch = "wasm32")]
+        let source = url.to_owned();
+        #[cfg(target_arch = "wasm32")]
+        ehttp::fetch(ehttp::Request::get(url), move |response| {
+            done(
+                source,
+                response
+                    .and_then(|response| {
+                        if response.ok {
+                            Ok(response.bytes)
+                        } else {
+                            Err(format!("{} {}", response.status, response.status_text))
+                        }
+                    })
+                    .map_err(Error::HttpError),
+            )
+        });
+    }
 
-            if let Some(ref error) = *self.error.lock() {
-                error.show(ui)
-            } else if self.events.lock().events.is_empty() {
-                ui.label("Load some data to get started");
+    /// reads the file, or stdin for `-`, line by line on a background thread while it is being
+    /// written, e.g. from a FIFO. The events are updated whenever no more lines are ready, until
+    /// the file ends or other events are loaded
+    #[cfg(not(target_arch = "wasm32"))]
+    fn stream(&self, ctx: &egui::Context) {
+        use std::io::BufRead;
+
+        let generation = self.loads.fetch_add(1, Ordering::Relaxed) + 1;
+        let loads = Arc::clone(&self.loads);
+        let events = Arc::clone(&self.events);
+        let errors = Arc::clone(&self.errors);
+        let filename = self.filename.clone();
+        let mut reader = jfon_viewer::LineReader::new(self.read_options());
+        let ctx = ctx.clone();
+        *events.lock() = Arc::default();
+        errors.lock().clear();
+        *self.loaded_from.lock() = (filename != "-").then(|| filename.clone());
+        log::info!("streaming {filename}");
+        std::thread::spawn(move || {
+            // opening a FIFO waits for its writer
+            let (input, source): (Box<dyn std::io::Read>, _) = if filename == "-" {
+                (Box::new(std::io::stdin()), "standard input".to_owned())
             } else {
-                Plot::new("bars")
-                    .legend(Legend::default())
-                    .data_aspect(10.0)
-                    .show(ui, |pui| {
-                        for ev in &self.events.lock().events {
-                            if self.view_by.matching(ev) {
-                                let id = ev.id;
-                                let e = BoxElem::new(
-                                    id as f64,
-                                    BoxSpread::new(
-                                        ev.span.start as f64,
-                                        ev.span.start as f64,
-                                        ev.span.start as f64,
-                                        (ev.span.start + ev.span.duration) as f64,
-                                        (ev.span.start + ev.span.duration) as f64,
-                                    ),
-                                )
-                                .box_width(1.0);
-                                pui.box_plot(
-                                    BoxPlot::new(vec![e]).horizontal().name(format!("{id}")),
-                                )
+                match std::fs::File::open(&filename) {
+                    Ok(file) => (Box::new(file), filename),
+                    Err(e) => {
+                        errors.lock().push(Error::IoError(e));
+                        return;
+                    }
+                }
+            };
+            let mut input = std::io::BufReader::with_capacity(1 << 16, input);
+            // the errors of single lines come first, those of pairing their starts and ends
+            // are replaced on every update
+            let mut line_errors = 0;
+            let mut new_errors = Vec::new();
+            let mut unlisted = Unlisted::default();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let done = match input.read_line(&mut line) {
+                    Ok(0) => true,
+                    Ok(_) => {
+                        let line = line.trim_end_matches(['\n', '\r']);
+                        match reader.push_line(line) {
+                            Some(e) if line_errors + new_errors.len() < jfon_viewer::MAX_ERRORS => {
+                                new_errors.push(e);
                             }
+                            Some(e) if e.is_warning() => unlisted.warnings += 1,
+                            Some(_) => unlisted.errors += 1,
+                            None => {}
                         }
-                    });
+                        false
+                    }
+                    Err(e) => {
+                        new_errors.push(Error::IoError(e));
+                        true
+                    }
+                };
+                if !done && !input.buffer().is_empty() {
+                    continue;
+                }
+
+                let (streamed, pairing_errors) = reader.events();
+                let mut events = events.lock();
+                if loads.load(Ordering::Relaxed) != generation {
+                    log::info!("stopped streaming {source}");
+                    return;
+                }
+                *events = Arc::new(Events {
+                    unlisted,
+                    ..streamed.with_source(source.clone())
+                });
+                let mut errors = errors.lock();
+                errors.truncate(line_errors);
+                errors.append(&mut new_errors);
+                line_errors = errors.len();
+                errors.extend(pairing_errors);
+                ctx.request_repaint();
+                if done {
+                    log::info!("{source} ended");
+                    return;
+                }
             }
         });
     }
+
+    /// what to call with the name of a file and its data once it was read in the background.
+    /// It replaces the events, or adds to them with `append`, and ends [`Analyzer::loading`].
+    /// Replaced events were read `from` there, see [`Analyzer::loaded_from`]
+    fn loader(
+        &self,
+        ctx: &egui::Context,
+        append: bool,
+        from: Option<String>,
+    ) -> impl FnOnce(String, Result<Vec<u8>, Error>) + Send {
+        let events = Arc::clone(&self.events);
+        let errors = Arc::clone(&self.errors);
+        let loading = Arc::clone(&self.loading);
+        let loads = Arc::clone(&self.loads);
+        let last_read = Arc::clone(&self.last_read);
+        let loaded_from = Arc::clone(&self.loaded_from);
+        let options = self.read_options();
+        let ctx = ctx.clone();
+        move |name, data| {
+            loads.fetch_add(1, Ordering::Relaxed);
+            let (e, errs) = match data {
+                Ok(data) => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let started = std::time::Instant::now();
+                    let (e, errs) = Events::read_bytes(&data, options);
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let took = Some(started.elapsed());
+                    #[cfg(target_arch = "wasm32")]
+                    let took = None;
+                    *last_read.lock() = Some(LastRead {
+                        name: name.clone(),
+                        events: e.events.len(),
+                        took,
+                    });
+                    (e.with_source(name), errs)
+                }
+                Err(e) => (Events::default(), vec![e]),
+            };
+            if append {
+                Arc::make_mut(&mut events.lock()).append(e);
+                errors.lock().extend(errs);
+                *loaded_from.lock() = None;
+            } else {
+                *events.lock() = Arc::new(e);
+                *errors.lock() = errs;
+                *loaded_from.lock() = from;
+            }
+            loading.store(false, Ordering::Relaxed);
+            ctx.request_repaint();
+        }
+    }
+
+    /// starts or stops watching the file as needed, and reloads it once it changed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        if !self.watch {
+            self.watcher = None;
+            return;
+        }
+        match &mut self.watcher {
+            Some(w) => {
+                if w.settled(ctx) {
+                    self.read(ctx);
+                }
+            }
+            None => match Watcher::new(&self.filename, ctx) {
+                Ok(w) => self.watcher = Some(w),
+                Err(e) => {
+                    self.watch = false;
+                    let e = std::io::Error::other(e);
+                    self.errors.lock().push(Error::IoError(e));
+                }
+            },
+        }
+    }
+
+    /// the events as horizontal bars over time
+    fn show_timeline(&mut self, ui: &mut egui::Ui, events: &Arc<Events>) -> egui::Response {
+        let offset = self.time_offset(events);
+        let now = match self.loaded {
+            Some((_, loaded_at)) if self.open_spans == OpenSpans::GrowToNow => {
+                let elapsed = ui.input(|i| i.time) - loaded_at;
+                events.end + elapsed * TimeUnit::S.nanos()
+            }
+            _ => events.end,
+        };
+        // every shown event along with where its bar ends
+        let shown: Vec<_> = events
+            .events
+            .iter()
+            .filter(|ev| self.visible(ev) && !(ev.open && self.open_spans == OpenSpans::Hide))
+            .map(|ev| {
+                let end = match self.open_spans {
+                    OpenSpans::ExtendToEnd | OpenSpans::GrowToNow if ev.open => {
+                        now.max(ev.span.start)
+                    }
+                    _ => ev.span.end(),
+                };
+                (ev, end)
+            })
+            .collect();
+        if self.open_spans == OpenSpans::GrowToNow && shown.iter().any(|(ev, _)| ev.open) {
+            ui.ctx().request_repaint();
+        }
+        // only drawn merged, the events stay as they are
+        let merged_gaps = match self.merge_gap {
+            Some(gap) if self.rows != Rows::Union => {
+                // labels and lanes are only known once merged, so those merge per label
+                let rows = self.rows;
+                merge_gaps(&shown, gap, |ev| match rows {
+                    Rows::Id => ev.id,
+                    Rows::Thread => ev.thread.unwrap_or(ev.id),
+                    _ => 0,
+                })
+            }
+            _ => Vec::new(),
+        };
+        let merged: Vec<_> = merged_gaps
+            .iter()
+            .filter(|&&(.., count)| count > 1)
+            .map(|&(first, end, _)| {
+                let mut ev = shown[first].0.clone();
+                ev.span.duration = end - ev.span.start;
+                ev
+            })
+            .collect();
+        let merged_counts: HashMap<_, _> = merged
+            .iter()
+            .zip(merged_gaps.iter().filter(|&&(.., count)| count > 1))
+            .map(|(ev, &(.., count))| ((ev.kind.as_str(), ev.id), count))
+            .collect();
+        let shown = if merged_gaps.is_empty() {
+            shown
+        } else {
+            let mut merged = merged.iter();
+            merged_gaps
+                .iter()
+                .map(|&(first, end, count)| match count {
+                    1 => shown[first],
+                    _ => (merged.next().expect("one per merged bar"), end),
+                })
+                .collect()
+        };
+        let rows: Vec<_> = match self.rows {
+            Rows::Id => shown.iter().map(|(ev, _)| ev.id as usize).collect(),
+            Rows::Thread => shown
+                .iter()
+                .map(|(ev, _)| ev.thread.unwrap_or(ev.id) as usize)
+                .collect(),
+            Rows::Label | Rows::Union => shown
+                .iter()
+                .map(|(ev, _)| {
+                    let label = events.labels.iter().position(|l| *l == ev.kind);
+                    label.unwrap_or(0)
+                })
+                .collect(),
+            Rows::Lanes => pack_lanes(&shown, &events.labels),
+        };
+        let rows = match self.row_order {
+            RowOrder::Sorted => rows,
+            RowOrder::FirstSeen => {
+                // the lanes of a label stay together
+                let groups: Vec<_> = match self.rows {
+                    Rows::Lanes => shown.iter().map(|(ev, _)| ev.kind.as_str()).collect(),
+                    _ => Vec::new(),
+                };
+                first_seen_rows(&rows, &groups)
+            }
+        };
+        let row_names = self.row_names(&shown, &rows);
+        let union = if self.rows == Rows::Union {
+            union_spans(&shown, &rows)
+        } else {
+            Vec::new()
+        };
+
+        self.advance_cursor(ui, &shown);
+        if let Some(index) = self.step_selection(ui, &shown) {
+            self.scroll_to(shown[index], rows[index]);
+        }
+
+        let mut plot = Plot::new("bars");
+        if !self.auto_aspect {
+            plot = plot.data_aspect(self.aspect);
+        }
+        // everything shown, with a small margin
+        let extent = shown.first().map(|&(first, _)| {
+            let start = first.span.start;
+            let end = shown.iter().map(|&(_, end)| end).fold(start, f64::max);
+            let top = rows.iter().copied().max().unwrap_or(0) as f64 + 0.5;
+            let bottom = rows.iter().copied().min().unwrap_or(0) as f64 - 0.5;
+            let (margin_x, margin_y) = ((end - start) * 0.05, (top - bottom) * 0.05);
+            PlotBounds::from_min_max(
+                [start - margin_x, bottom - margin_y],
+                [end + margin_x, top + margin_y],
+            )
+        });
+        // bars outside of the view are not drawn, so the plot can't fit itself to them
+        if let Some(extent) = extent {
+            let ([min_x, min_y], [max_x, max_y]) = (extent.min(), extent.max());
+            plot = plot
+                .include_x(min_x)
+                .include_x(max_x)
+                .include_y(min_y)
+                .include_y(max_y);
+        }
+        let fit = std::mem::take(&mut self.fit);
+        let concurrency = if self.show_concurrency {
+            let steps = &self.stats(events).concurrency;
+            // drawn as steps
+            let points: Vec<_> = steps
+                .iter()
+                .scan(0, |previous, &(time, count)| {
+                    let step = [[time, *previous as f64], [time, count as f64]];
+                    *previous = count;
+                    Some(step)
+                })
+                .flatten()
+                .collect();
+            Some(Line::new(points).name("concurrency").color(Color32::GRAY))
+        } else {
+            None
+        };
+        let gaps: Vec<_> = match extent.filter(|_| self.show_gaps) {
+            Some(extent) => {
+                let (bottom, top) = (extent.min()[1], extent.max()[1]);
+                let gaps = &self.stats(events).gaps;
+                gaps.iter()
+                    .take(LONGEST_GAPS)
+                    .map(|&(from, to)| {
+                        let corners = vec![[from, bottom], [to, bottom], [to, top], [from, top]];
+                        Polygon::new(corners)
+                            .color(Color32::GRAY)
+                            .fill_alpha(0.15)
+                            .stroke(Stroke::NONE)
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        self.show_legend(ui, events);
+        let recenter = extent.and_then(|extent| self.show_minimap(ui, &shown, extent));
+        let cursor = self.cursor;
+        let cursor_color = ui.visuals().strong_text_color();
+        let crosshair = self.crosshair;
+        let bar_height = self.bar_height;
+        let warn_color = ui.visuals().warn_fg_color;
+        let unit = self.time_format();
+        plot = plot
+            .x_axis_formatter(move |x, range| unit.format_tick(x + offset, range))
+            .y_axis_formatter(move |y, _| {
+                let row = y.round();
+                match row_names.get(&(row as usize)) {
+                    Some(name) if (y - row).abs() < 1e-6 && row >= 0.0 => name.clone(),
+                    _ => String::new(),
+                }
+            });
+        let zoom_to = self.zoom_to.take();
+        let response = plot.show(ui, |pui| {
+            if let Some(extent) = extent.filter(|_| fit) {
+                pui.set_plot_bounds(extent);
+            }
+            if let Some(bounds) = zoom_to {
+                pui.set_plot_bounds(bounds);
+            }
+            // the bounds are those of the last frame. Keep a viewport worth of bars around
+            // them, so that nothing is missing while zooming out or dragging
+            let bounds = pui.plot_bounds();
+            if let Some(x) = recenter.filter(|_| !fit) {
+                let (min, max) = (bounds.min(), bounds.max());
+                let half = bounds.width() / 2.0;
+                pui.set_plot_bounds(PlotBounds::from_min_max(
+                    [x - half, min[1]],
+                    [x + half, max[1]],
+                ));
+            }
+            let (min, max) = (bounds.min(), bounds.max());
+            let (width, height) = (bounds.width(), bounds.height());
+            let in_view = |start: f64, end: f64, row: usize| {
+                let row = row as f64;
+                end >= min[0] - width
+                    && start <= max[0] + width
+                    && row + 0.5 >= min[1] - height
+                    && row - 0.5 <= max[1] + height
+            };
+
+            // the event under the pointer, with bounds around it
+            let hit = || {
+                let pos = pui.pointer_coordinate()?;
+                // marks have no width, so they are hit from a little bit around them
+                let slack = width * 0.005;
+                shown
+                    .iter()
+                    .zip(&rows)
+                    .find(|&(&(ev, end), &row)| {
+                        let (start, end) = if ev.mark {
+                            (ev.span.start - slack, ev.span.start + slack)
+                        } else {
+                            (ev.span.start, end)
+                        };
+                        (pos.y - row as f64).abs() <= bar_height / 2.0
+                            && (start..=end).contains(&pos.x)
+                    })
+                    .map(|(&(ev, end), &row)| {
+                        let margin = ((end - ev.span.start) * 0.1).max(slack);
+                        let row = row as f64;
+                        let bounds = PlotBounds::from_min_max(
+                            [ev.span.start - margin, row - 2.0],
+                            [end + margin, row + 2.0],
+                        );
+                        ((ev.kind.clone(), ev.id), bounds)
+                    })
+            };
+            // `Some(None)` if empty space was clicked. The union has no events to select
+            let selectable = self.rows != Rows::Union;
+            let clicked = (pui.plot_clicked() && selectable).then(|| hit().map(|(event, _)| event));
+            let right_clicked = (pui.plot_secondary_clicked() && selectable).then(hit);
+            // every event active at the hovered time, along with how much of it is left
+            let hovered = crosshair
+                .then(|| pui.pointer_coordinate())
+                .flatten()
+                .map(|pos| {
+                    pui.vline(VLine::new(pos.x).color(cursor_color));
+                    let active: Vec<_> = shown
+                        .iter()
+                        .filter(|(ev, end)| !ev.mark && (ev.span.start..=*end).contains(&pos.x))
+                        .map(|&(ev, end)| (ev, end - pos.x))
+                        .collect();
+                    (pos.x, active)
+                });
+
+            let mut boxes = HashMap::<_, Vec<_>>::new();
+            let mut marks = HashMap::<_, Vec<_>>::new();
+            let mut dimmed_marks = HashMap::<_, Vec<_>>::new();
+            let ahead = |start: f64| cursor.is_some_and(|cursor| start > cursor);
+            // only where the bar ends on screen, the tooltips keep the real times
+            let min_width = if self.min_bar_width {
+                let origin = pui.plot_from_screen(egui::Pos2::ZERO).x;
+                (pui.plot_from_screen(egui::pos2(MIN_BAR_PIXELS, 0.0)).x - origin).abs()
+            } else {
+                0.0
+            };
+            let drawn_end = |start: f64, end: f64| end.max(start + min_width);
+            let mut selected_mark = None;
+            // bars drawn, and those in view that were left out because of `max_bars`
+            let (mut bars, mut left_out) = (0, 0);
+            let full = |bars: usize| self.max_bars != 0 && bars >= self.max_bars;
+            // more opaque the more events were merged
+            let most = union.iter().map(|&(.., count)| count).max().unwrap_or(1);
+            for &(label, row, start, end, count) in &union {
+                if !in_view(start, end, row) {
+                    continue;
+                }
+                if full(bars) {
+                    left_out += 1;
+                    continue;
+                }
+                bars += 1;
+                let color = self.event_color(label);
+                let density = (count as f32).ln_1p() / (most as f32).ln_1p();
+                let drawn = drawn_end(start, end);
+                let e = BoxElem::new(
+                    row as f64,
+                    BoxSpread::new(start, start, start, drawn, drawn),
+                )
+                .box_width(bar_height)
+                .fill(color.linear_multiply(0.2 + 0.8 * density))
+                .name(format!(
+                    "kind: {label}\nevents: {count}\nstart: {}\nend: {}",
+                    unit.format(start + offset),
+                    unit.format(end + offset),
+                ));
+                boxes.entry(label).or_default().push(e);
+            }
+            let events_shown = if self.rows == Rows::Union {
+                0
+            } else {
+                shown.len()
+            };
+            for (&(ev, end), &row) in shown.iter().zip(&rows).take(events_shown) {
+                let start = ev.span.start;
+                if !in_view(start, end, row) {
+                    continue;
+                }
+                if ev.mark {
+                    let point = [start, row as f64];
+                    if self.is_selected(ev) {
+                        selected_mark = Some(point);
+                    }
+                    let marks = if ahead(start) {
+                        &mut dimmed_marks
+                    } else {
+                        &mut marks
+                    };
+                    marks.entry(ev.kind.as_str()).or_default().push(point);
+                    continue;
+                }
+                if full(bars) {
+                    left_out += 1;
+                    continue;
+                }
+                bars += 1;
+                let drawn = drawn_end(start, end);
+                let mut e = BoxElem::new(
+                    row as f64,
+                    BoxSpread::new(start, start, start, drawn, drawn),
+                )
+                .box_width(bar_height)
+                .name(match merged_counts.get(&(ev.kind.as_str(), ev.id)) {
+                    Some(count) => {
+                        format!("{}\nmerged: {count} events", self.tooltip(ev, events))
+                    }
+                    None => self.tooltip(ev, events),
+                });
+                if ev.open {
+                    // only outline open events, so that they stand out from real ones
+                    let color = self.event_color(&ev.kind);
+                    e = e
+                        .fill(color.linear_multiply(0.05))
+                        .stroke(Stroke::new(1.0, color.linear_multiply(0.6)));
+                }
+                if ahead(start) {
+                    let color = self.event_color(&ev.kind).linear_multiply(0.15);
+                    e = e.fill(color).stroke(Stroke::new(1.0, color));
+                }
+                if self.show_health && ev.suspicion.is_some() {
+                    e = e.stroke(Stroke::new(2.0, warn_color));
+                }
+                if self.is_selected(ev) {
+                    e = e
+                        .fill(SELECTED_COLOR.linear_multiply(0.5))
+                        .stroke(Stroke::new(2.0, SELECTED_COLOR));
+                }
+                boxes.entry(ev.kind.as_str()).or_default().push(e);
+            }
+
+            for label in &events.labels {
+                if let Some(boxes) = boxes.remove(label.as_str()) {
+                    pui.box_plot(
+                        BoxPlot::new(boxes)
+                            .horizontal()
+                            .color(self.event_color(label))
+                            .name(label)
+                            // the crosshair lists them instead
+                            .element_formatter(Box::new(move |e, _| {
+                                if crosshair {
+                                    String::new()
+                                } else {
+                                    e.name.clone()
+                                }
+                            })),
+                    )
+                }
+                if let Some(marks) = marks.remove(label.as_str()) {
+                    pui.points(
+                        Points::new(marks)
+                            .shape(MarkerShape::Diamond)
+                            .filled(true)
+                            .radius(5.0)
+                            .color(self.event_color(label))
+                            .name(label),
+                    )
+                }
+                if let Some(marks) = dimmed_marks.remove(label.as_str()) {
+                    pui.points(
+                        Points::new(marks)
+                            .shape(MarkerShape::Diamond)
+                            .filled(true)
+                            .radius(5.0)
+                            .color(self.event_color(label).linear_multiply(0.15))
+                            .name(label),
+                    )
+                }
+            }
+            if let Some(cursor) = cursor {
+                pui.vline(VLine::new(cursor).color(cursor_color));
+            }
+            if let Some(point) = selected_mark {
+                pui.points(
+                    Points::new(vec![point])
+                        .shape(MarkerShape::Diamond)
+                        .filled(true)
+                        .radius(7.0)
+                        .color(SELECTED_COLOR),
+                );
+            }
+            for gap in gaps {
+                pui.polygon(gap);
+            }
+            if let Some(concurrency) = concurrency {
+                pui.line(concurrency);
+            }
+            (clicked, right_clicked, bounds, hovered, (bars, left_out))
+        });
+        let (clicked, right_clicked, bounds, hovered, (bars, left_out)) = response.inner;
+        if left_out > 0 {
+            let rect = response.response.rect;
+            ui.painter_at(rect).text(
+                rect.center_top() + egui::vec2(0.0, 8.0),
+                egui::Align2::CENTER_TOP,
+                format!(
+                    "showing {bars} of {} events — zoom or filter to see more",
+                    bars + left_out
+                ),
+                egui::TextStyle::Body.resolve(ui.style()),
+                ui.visuals().warn_fg_color,
+            );
+        }
+        if let Some((time, active)) = hovered.filter(|_| response.response.hovered()) {
+            egui::show_tooltip_at_pointer(ui.ctx(), egui::Id::new("crosshair"), |ui| {
+                ui.strong(unit.format(time + offset));
+                if active.is_empty() {
+                    ui.label("no active events");
+                }
+                egui::Grid::new("crosshair_events")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for &(ev, left) in active.iter().take(CROSSHAIR_EVENTS) {
+                            ui.label(RichText::new(&ev.kind).color(self.event_color(&ev.kind)));
+                            ui.label(ev.id.to_string());
+                            ui.label(if ev.open {
+                                "no end recorded".to_owned()
+                            } else {
+                                format!("{} left", unit.format(left))
+                            });
+                            ui.end_row();
+                        }
+                    });
+                if active.len() > CROSSHAIR_EVENTS {
+                    ui.weak(format!("and {} more", active.len() - CROSSHAIR_EVENTS));
+                }
+            });
+        }
+        if let Some(clicked) = clicked {
+            self.selected = clicked;
+        }
+        if let Some(right_clicked) = right_clicked {
+            self.context_event = right_clicked;
+        }
+        self.viewport = Some(bounds);
+        response
+            .response
+            .context_menu(|ui| self.show_context_menu(ui, events))
+    }
+
+    /// quick actions for the right-clicked event
+    fn show_context_menu(&mut self, ui: &mut egui::Ui, events: &Events) {
+        let Some(((kind, id), bounds)) = self.context_event.clone() else {
+            ui.weak("right-click an event for its actions");
+            return;
+        };
+        let Some(ev) = events
+            .events
+            .iter()
+            .find(|ev| ev.kind == kind && ev.id == id)
+        else {
+            ui.close_menu();
+            return;
+        };
+        if ui.button("Filter to this label").clicked() {
+            self.view_by = ViewBy::Label(kind.clone());
+            ui.close_menu();
+        }
+        if ui.button("Hide this label").clicked() {
+            self.hidden_labels.insert(kind.clone());
+            ui.close_menu();
+        }
+        if ui.button("Zoom to this event").clicked() {
+            self.zoom_to = Some(bounds);
+            ui.close_menu();
+        }
+        if ui.button("Copy details").clicked() {
+            let row = self.copied_row(ev, events);
+            ui.output_mut(|o| o.copied_text = row);
+            ui.close_menu();
+        }
+    }
+
+    /// the kind, id, start and duration of `ev` as tab separated text
+    fn copied_row(&self, ev: &Event, events: &Events) -> String {
+        let start = round(ev.span.start + self.event_offset(events, ev), self.decimals);
+        let duration = round(ev.span.duration, self.decimals);
+        format!("{}\t{}\t{start}\t{duration}", ev.kind, ev.id)
+    }
+
+    /// every label, which hides or shows its events when clicked
+    fn show_legend(&mut self, ui: &mut egui::Ui, events: &Events) {
+        egui::ScrollArea::vertical()
+            .id_source("legend")
+            .max_height(60.0)
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for label in &events.labels {
+                        let hidden = self.hidden_labels.contains(label);
+                        let color = if hidden {
+                            ui.visuals().weak_text_color()
+                        } else {
+                            self.event_color(label)
+                        };
+                        let text = RichText::new(label).color(color);
+                        let text = if hidden { text.strikethrough() } else { text };
+                        if ui.selectable_label(!hidden, text).clicked() {
+                            if hidden {
+                                self.hidden_labels.remove(label);
+                            } else {
+                                self.hidden_labels.insert(label.clone());
+                            }
+                        }
+                    }
+                });
+            });
+    }
+
+    /// the arrow keys select the previous or next of the `shown` events by start, Home and End
+    /// the first and last. Returns the index of the newly selected one
+    fn step_selection(&mut self, ui: &egui::Ui, shown: &[(&Event, f64)]) -> Option<usize> {
+        use egui::{Key, Modifiers};
+
+        // the keys belong to whatever has focus, like a slider
+        if shown.is_empty() || ui.memory(|m| m.focus().is_some()) {
+            return None;
+        }
+        let current = shown.iter().position(|(ev, _)| self.is_selected(ev));
+        let last = shown.len() - 1;
+        let index = ui.ctx().input_mut(|i| {
+            let mut pressed = |key| i.consume_key(Modifiers::NONE, key);
+            if pressed(Key::Home) {
+                Some(0)
+            } else if pressed(Key::End) {
+                Some(last)
+            } else if pressed(Key::ArrowRight) || pressed(Key::ArrowDown) {
+                Some(current.map_or(0, |current| (current + 1).min(last)))
+            } else if pressed(Key::ArrowLeft) || pressed(Key::ArrowUp) {
+                Some(current.map_or(0, |current| current.saturating_sub(1)))
+            } else {
+                None
+            }
+        })?;
+        let (ev, _) = shown[index];
+        self.selected = Some((ev.kind.clone(), ev.id));
+        Some(index)
+    }
+
+    /// moves the timeline so that the event ending at `end` in `row` is in view, keeping its zoom
+    fn scroll_to(&mut self, (ev, end): (&Event, f64), row: usize) {
+        let Some(viewport) = &self.viewport else {
+            return;
+        };
+        let (min, max) = (viewport.min(), viewport.max());
+        let row = row as f64;
+        let visible = ev.span.start >= min[0] && end <= max[0] && row >= min[1] && row <= max[1];
+        if visible {
+            return;
+        }
+        let (half_width, half_height) = (viewport.width() / 2.0, viewport.height() / 2.0);
+        // long events are shown from their start
+        let x = if end - ev.span.start > viewport.width() {
+            ev.span.start + half_width * 0.9
+        } else {
+            (ev.span.start + end) / 2.0
+        };
+        self.zoom_to = Some(PlotBounds::from_min_max(
+            [x - half_width, row - half_height],
+            [x + half_width, row + half_height],
+        ));
+    }
+
+    /// moves the playback cursor across the `shown` events, and ends playback once it reached
+    /// the last of them
+    fn advance_cursor(&mut self, ui: &egui::Ui, shown: &[(&Event, f64)]) {
+        let Some(&(first, _)) = shown.first().filter(|_| self.playing) else {
+            return;
+        };
+        let start = first.span.start;
+        let end = shown.iter().map(|&(_, end)| end).fold(start, f64::max);
+        let dt = ui.input(|i| i.stable_dt) as f64;
+        let cursor =
+            self.cursor.unwrap_or(start).max(start) + dt * (end - start) / self.play_seconds;
+        if cursor < end {
+            self.cursor = Some(cursor);
+            ui.ctx().request_repaint();
+        } else {
+            // everything is revealed
+            self.cursor = None;
+            self.playing = false;
+        }
+    }
+
+    /// a strip showing how many of the `shown` events start over the whole `extent`, with the
+    /// part of it in view highlighted. Returns where it was clicked or dragged to, so that the
+    /// view can be moved there
+    fn show_minimap(
+        &self,
+        ui: &mut egui::Ui,
+        shown: &[(&Event, f64)],
+        extent: PlotBounds,
+    ) -> Option<f64> {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), 24.0),
+            egui::Sense::click_and_drag(),
+        );
+        let (min_x, max_x) = (extent.min()[0], extent.max()[0]);
+        if max_x <= min_x {
+            return None;
+        }
+        let to_screen =
+            |x: f64| rect.left() + ((x - min_x) / (max_x - min_x)) as f32 * rect.width();
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let last = rect.width().max(1.0) as usize - 1;
+        let mut columns = vec![0_usize; last + 1];
+        for (ev, _) in shown {
+            let column = (to_screen(ev.span.start) - rect.left()).max(0.0) as usize;
+            columns[column.min(last)] += 1;
+        }
+        // logarithmic, so that a few events next to many are still visible
+        let most = (columns.iter().copied().max().unwrap_or(0) as f32).ln_1p();
+        let stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+        for (column, &count) in columns.iter().enumerate().filter(|(_, &count)| count > 0) {
+            let x = rect.left() + column as f32 + 0.5;
+            let height = rect.height() * (count as f32).ln_1p() / most;
+            painter.line_segment(
+                [
+                    egui::pos2(x, rect.bottom()),
+                    egui::pos2(x, rect.bottom() - height),
+                ],
+                stroke,
+            );
+        }
+
+        if let Some(viewport) = self.viewport {
+            let (left, right) = (to_screen(viewport.min()[0]), to_screen(viewport.max()[0]));
+            let view = egui::Rect::from_x_y_ranges(left..=right, rect.y_range());
+            let selection = ui.visuals().selection;
+            painter.rect_filled(view, 0.0, selection.bg_fill.linear_multiply(0.3));
+            painter.rect_stroke(view, 0.0, selection.stroke);
+        }
+
+        let pos = response.interact_pointer_pos()?;
+        let x = min_x + f64::from((pos.x - rect.left()) / rect.width()) * (max_x - min_x);
+        Some(x.clamp(min_x, max_x))
+    }
+
+    /// the color of `label`, dimmed if it does not match the search
+    fn event_color(&self, label: &str) -> Color32 {
+        let color = self.palette.color(label);
+        let search = self.search.to_lowercase();
+        if label.to_lowercase().contains(&search) {
+            color
+        } else {
+            color.linear_multiply(0.15)
+        }
+    }
+
+    /// [`Event::tooltip`], along with the file of `ev` once there are several
+    fn tooltip(&self, ev: &Event, events: &Events) -> String {
+        let tooltip = ev.tooltip(self.event_offset(events, ev), self.time_format());
+        match events.sources.get(ev.source) {
+            Some(source) if events.sources.len() > 1 => format!("{tooltip}\nsource: {source}"),
+            _ => tooltip,
+        }
+    }
+
+    fn is_selected(&self, ev: &Event) -> bool {
+        self.selected
+            .as_ref()
+            .is_some_and(|(kind, id)| *kind == ev.kind && *id == ev.id)
+    }
+
+    /// forgets the selected event once it is filtered out or no longer loaded
+    fn validate_selection(&mut self, events: &Events) {
+        let valid = events
+            .events
+            .iter()
+            .any(|ev| self.is_selected(ev) && self.visible(ev));
+        if !valid {
+            self.selected = None;
+        }
+    }
+
+    /// asks what to do with a file over the size limit, if there is one
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_too_large(&mut self, ui: &mut egui::Ui) {
+        let Some(file) = self.too_large.lock().take() else {
+            return;
+        };
+        let (mut open, mut stream, mut cancel) = (false, false, false);
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!(
+                    "{} is {} MiB, more than the limit of {} MiB",
+                    file.path,
+                    file.size >> 20,
+                    self.max_file_mib
+                ),
+            );
+            open = ui.button("Open anyway").clicked();
+            if !file.append {
+                stream = ui
+                    .button("Stream")
+                    .on_hover_text(
+                        "read it line by line instead of all at once, like --stream. This only \
+                         supports the line and compact formats",
+                    )
+                    .clicked();
+            }
+            cancel = ui.button("Cancel").clicked();
+        });
+        if stream {
+            self.filename = file.path;
+            self.stream(ui.ctx());
+        } else if open {
+            remember_file(&self.recent, file.path.as_ref());
+            if !file.append {
+                self.filename = file.path.clone();
+            }
+            self.loading.store(true, Ordering::Relaxed);
+            let from = (!file.append).then(|| file.path.clone());
+            let done = self.loader(ui.ctx(), file.append, from);
+            std::thread::spawn(move || {
+                let data = std::fs::read(&file.path).map_err(Error::IoError);
+                done(file.path, data)
+            });
+        } else if !cancel {
+            *self.too_large.lock() = Some(file);
+        }
+    }
+
+    /// lets the user pick how files are read
+    fn show_format(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("separators").num_columns(2).show(ui, |ui| {
+            let separators = &mut self.separators;
+            for (name, separator) in [
+                ("label separator", &mut separators.label),
+                ("field separator", &mut separators.field),
+            ] {
+                ui.label(name);
+                ComboBox::from_id_source(name)
+                    .selected_text(Separators::name(*separator))
+                    .show_ui(ui, |ui| {
+                        for (choice, choice_name) in Separators::CHOICES {
+                            ui.selectable_value(separator, choice, choice_name);
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+        if ui.button("Reset").clicked() {
+            self.separators = Separators::default();
+        }
+        ui.separator();
+        let fold_case = ui
+            .checkbox(&mut self.fold_case, "Ignore case of labels")
+            .on_hover_text("treat labels that only differ in case as the same");
+        if fold_case.changed() && self.fold_case {
+            // so that the selection still matches the lowercase labels
+            self.view_by = match &self.view_by {
+                ViewBy::Label(l) => ViewBy::Label(l.to_lowercase()),
+                ViewBy::Labels(ls) => ViewBy::Labels(ls.iter().map(|l| l.to_lowercase()).collect()),
+                view_by => view_by.clone(),
+            };
+            self.hidden_labels = self
+                .hidden_labels
+                .iter()
+                .map(|l| l.to_lowercase())
+                .collect();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("ask before reading files over");
+                ui.add(egui::DragValue::new(&mut self.max_file_mib).suffix(" MiB"))
+                    .on_hover_text("0 reads files of any size");
+            });
+        }
+    }
+
+    /// a summary of what is loaded and how much of it is visible
+    fn show_status(&self, ui: &mut egui::Ui, events: &Events) {
+        let visible = self.visible_events(events).count();
+        let offset = self.time_offset(events);
+        ui.horizontal(|ui| {
+            if self.loading.load(Ordering::Relaxed) {
+                ui.spinner();
+                ui.label("Loading…");
+                ui.separator();
+            }
+            ui.label(format!(
+                "{} events ({visible} visible), {} labels, from {} to {}",
+                events.events.len(),
+                events.labels.len(),
+                self.time_format().format(offset),
+                self.time_format().format(events.end + offset),
+            ));
+            if events.sources.len() > 1 {
+                ui.separator();
+                let mut counts = vec![0; events.sources.len()];
+                for ev in &events.events {
+                    counts[ev.source] += 1;
+                }
+                let per_file: Vec<_> = events
+                    .sources
+                    .iter()
+                    .zip(counts)
+                    .map(|(source, count)| format!("{source}: {count} events"))
+                    .collect();
+                ui.label(format!("{} files", events.sources.len()))
+                    .on_hover_text(per_file.join("\n"));
+            }
+            if let Some(LastRead { name, events, took }) = &*self.last_read.lock() {
+                ui.separator();
+                ui.label(match took {
+                    Some(took) => format!(
+                        "read {events} events from {name} in {:.1} ms",
+                        took.as_secs_f64() * 1000.0
+                    ),
+                    None => format!("read {events} events from {name}"),
+                });
+            }
+        });
+    }
+
+    /// everything known about the selected event
+    fn show_details(&mut self, ui: &mut egui::Ui, events: &Events) {
+        let Some(ev) = events.events.iter().find(|ev| self.is_selected(ev)) else {
+            return;
+        };
+        let unit = self.time_format();
+        let time = |time: f64| unit.format(time + events.offset_of(ev.source));
+        let mut close = false;
+        ui.horizontal(|ui| {
+            egui::Grid::new("details").num_columns(2).show(ui, |ui| {
+                ui.strong("kind");
+                ui.label(RichText::new(&ev.kind).color(self.palette.color(&ev.kind)));
+                ui.end_row();
+                ui.strong("id");
+                ui.label(ev.id.to_string());
+                ui.end_row();
+                if let Some(thread) = ev.thread {
+                    ui.strong("thread");
+                    ui.label(thread.to_string());
+                    ui.end_row();
+                }
+                ui.strong("start");
+                ui.label(if ev.orphan {
+                    "no start recorded".to_owned()
+                } else {
+                    time(ev.span.start)
+                });
+                ui.end_row();
+                ui.strong("duration");
+                ui.label(if ev.complete() {
+                    unit.format(ev.span.duration)
+                } else {
+                    "-".to_owned()
+                });
+                ui.end_row();
+                ui.strong("end");
+                ui.label(if ev.open {
+                    "no end recorded".to_owned()
+                } else {
+                    time(ev.span.end())
+                });
+                ui.end_row();
+                if let Some(lines) = ev.lines() {
+                    ui.strong("lines");
+                    ui.label(lines);
+                    ui.end_row();
+                }
+            });
+            ui.vertical(|ui| {
+                close = ui.button("✖").on_hover_text("deselect").clicked();
+                if ui
+                    .button("Copy")
+                    .on_hover_text("copy the kind, id, start and duration for a spreadsheet")
+                    .clicked()
+                {
+                    let row = self.copied_row(ev, events);
+                    ui.output_mut(|o| o.copied_text = row);
+                }
+            });
+        });
+        if close {
+            self.selected = None;
+        }
+    }
+
+    /// how many of the visible events took how long
+    fn show_histogram(&self, ui: &mut egui::Ui, events: &Events) -> egui::Response {
+        // open events have no real duration
+        let durations: Vec<_> = events
+            .events
+            .iter()
+            .filter(|ev| self.visible(ev) && ev.complete())
+            .map(|ev| ev.span.duration)
+            .collect();
+        let min = durations.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = durations.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        // no durations at all
+        let (min, max) = if min <= max { (min, max) } else { (0.0, 0.0) };
+        let bins = self.bins.max(1);
+        let width = if max > min {
+            (max - min) / bins as f64
+        } else {
+            1.0
+        };
+
+        let mut counts = vec![0; bins];
+        for d in durations {
+            let bin = ((d - min) / width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        let unit = self.time_format();
+        let bars = counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let from = min + i as f64 * width;
+                let name = format!(
+                    "{} – {}\n{count} events",
+                    unit.format(from),
+                    unit.format(from + width)
+                );
+                egui::plot::Bar::new(from + width / 2.0, count as f64)
+                    .width(width)
+                    .name(name)
+            })
+            .collect();
+
+        let plot =
+            Plot::new("histogram").x_axis_formatter(move |x, range| unit.format_tick(x, range));
+        plot.show(ui, |pui| {
+            pui.bar_chart(
+                egui::plot::BarChart::new(bars)
+                    .name("durations")
+                    .element_formatter(Box::new(|b, _| b.name.clone())),
+            )
+        })
+        .response
+    }
+
+    /// what to call each of the `rows` of the `shown` events on the y axis
+    fn row_names(&self, shown: &[(&Event, f64)], rows: &[usize]) -> HashMap<usize, String> {
+        // the lanes of a label are numbered from its first one
+        let mut first_lane = HashMap::<&str, usize>::new();
+        if self.rows == Rows::Lanes {
+            for (&(ev, _), &row) in shown.iter().zip(rows) {
+                let first = first_lane.entry(ev.kind.as_str()).or_insert(row);
+                *first = row.min(*first);
+            }
+        }
+        let mut names = HashMap::new();
+        for (&(ev, _), &row) in shown.iter().zip(rows) {
+            names.entry(row).or_insert_with(|| match self.rows {
+                Rows::Id => ev.id.to_string(),
+                Rows::Thread => match ev.thread {
+                    Some(thread) => format!("thread {thread}"),
+                    None => ev.id.to_string(),
+                },
+                Rows::Label | Rows::Union => ev.kind.clone(),
+                Rows::Lanes => {
+                    let lane = row - first_lane[ev.kind.as_str()] + 1;
+                    format!("{} {lane}", ev.kind)
+                }
+            });
+        }
+        names
+    }
+
+    /// one box per label, spanning the quartiles of the durations of its visible events, with
+    /// whiskers to the shortest and longest
+    fn show_box_plot(&mut self, ui: &mut egui::Ui, events: &Arc<Events>) -> egui::Response {
+        let (unit, palette) = (self.time_format(), self.palette);
+        let stats = self.stats(events);
+        let labels: Vec<_> = stats
+            .labels
+            .iter()
+            .filter(|stats| stats.durations.is_some())
+            .map(|stats| stats.label.clone())
+            .collect();
+        let boxes: Vec<_> = stats
+            .labels
+            .iter()
+            .filter_map(|stats| Some((&stats.label, stats.durations?)))
+            .enumerate()
+            .map(|(row, (label, d))| {
+                let name = format!(
+                    "{label}\n{} events\nmin {}\nq1 {}\nmedian {}\nq3 {}\nmax {}",
+                    d.count,
+                    unit.format(d.min),
+                    unit.format(d.q1),
+                    unit.format(d.median),
+                    unit.format(d.q3),
+                    unit.format(d.max)
+                );
+                let color = palette.color(label);
+                BoxElem::new(
+                    row as f64,
+                    BoxSpread::new(d.min, d.q1, d.median, d.q3, d.max),
+                )
+                .name(name)
+                .fill(color.linear_multiply(0.5))
+                .stroke(Stroke::new(1.0, color))
+            })
+            .collect();
+
+        let plot = Plot::new("box_plot")
+            .x_axis_formatter(move |x, range| unit.format_tick(x, range))
+            .y_axis_formatter(move |y, _| {
+                let row = y.round();
+                match labels.get(row as usize) {
+                    Some(label) if (y - row).abs() < 1e-6 && row >= 0.0 => label.clone(),
+                    _ => String::new(),
+                }
+            });
+        plot.show(ui, |pui| {
+            pui.box_plot(
+                BoxPlot::new(boxes)
+                    .horizontal()
+                    .name("durations")
+                    .element_formatter(Box::new(|b, _| b.name.clone())),
+            )
+        })
+        .response
+    }
+
+    /// a summary of the visible events, then one row per label with their statistics and one
+    /// for all of them
+    fn show_stats(&mut self, ui: &mut egui::Ui, events: &Arc<Events>) {
+        const HEADERS: [&str; 10] = [
+            "label", "count", "open", "min", "max", "mean", "p50", "p90", "p95", "p99",
+        ];
+
+        let (unit, palette, decimals) = (self.time_format(), self.palette, self.decimals);
+        let offset = self.time_offset(events);
+        let stats = self.stats(events);
+        ui.label(format!(
+            "{} events with {} labels and {} ids, {} of them without an end",
+            stats.total.count,
+            stats.labels.len(),
+            stats.ids,
+            stats.total.open,
+        ));
+        if let Some((from, to)) = stats.span {
+            ui.label(format!(
+                "covering {} from {} to {}",
+                unit.format(to - from),
+                unit.format(from + offset),
+                unit.format(to + offset)
+            ));
+        }
+        egui::Grid::new("stats").striped(true).show(ui, |ui| {
+            for header in HEADERS {
+                ui.strong(header);
+            }
+            ui.end_row();
+
+            let row = |ui: &mut egui::Ui, stats: &LabelStats, label: RichText| {
+                ui.label(label);
+                ui.label(stats.count.to_string());
+                ui.label(stats.open.to_string());
+                match stats.durations {
+                    Some(d) => {
+                        for d in [d.min, d.max, d.mean, d.median, d.p90, d.p95, d.p99] {
+                            ui.label(unit.format(d));
+                        }
+                    }
+                    None => {
+                        for _ in 3..HEADERS.len() {
+                            ui.label("-");
+                        }
+                    }
+                }
+                ui.end_row();
+            };
+            for stats in &stats.labels {
+                let label = RichText::new(&stats.label).color(palette.color(&stats.label));
+                row(ui, stats, label);
+            }
+            row(ui, &stats.total, RichText::new(&stats.total.label).strong());
+        });
+
+        if ui
+            .button("Copy")
+            .on_hover_text("copy the table as tab separated text for a spreadsheet")
+            .clicked()
+        {
+            let mut table = HEADERS.join("\t");
+            for stats in stats.labels.iter().chain([&stats.total]) {
+                let durations = match stats.durations {
+                    Some(d) => [d.min, d.max, d.mean, d.median, d.p90, d.p95, d.p99]
+                        .map(|d| round(d, decimals).to_string())
+                        .join("\t"),
+                    // empty cells rather than dashes, so that spreadsheets don't see text
+                    None => "\t".repeat(HEADERS.len() - 4),
+                };
+                table += &format!(
+                    "\n{}\t{}\t{}\t{durations}",
+                    stats.label, stats.count, stats.open
+                );
+            }
+            ui.output_mut(|o| o.copied_text = table);
+        }
+
+        ui.separator();
+        if let Some((count, from, to)) = peak_concurrency(&stats.concurrency) {
+            ui.label(format!(
+                "at most {count} events at once, from {} to {}",
+                unit.format(from + offset),
+                unit.format(to + offset)
+            ));
+        }
+        if !stats.gaps.is_empty() {
+            ui.label("longest times without any active event:");
+            for &(from, to) in stats.gaps.iter().take(LONGEST_GAPS) {
+                ui.label(format!(
+                    "{} from {} to {}",
+                    unit.format(to - from),
+                    unit.format(from + offset),
+                    unit.format(to + offset)
+                ));
+            }
+        }
+    }
+
+    /// the warnings found while reading, and the visible events with a [`jfon_viewer::Suspicion`]. Clicking
+    /// one selects it
+    fn show_health(&mut self, ui: &mut egui::Ui, events: &Events) {
+        let warnings = self.errors.lock().iter().filter(|e| e.is_warning()).count();
+        let visible: Vec<_> = events.events.iter().filter(|ev| self.visible(ev)).collect();
+        let orphans = visible.iter().filter(|ev| ev.orphan).count();
+        let open = visible.iter().filter(|ev| ev.open).count();
+        ui.label(format!("{warnings} warnings while reading"));
+        ui.label(format!("{orphans} events without a start"));
+        ui.label(format!("{open} events without an end"));
+        let suspicious: Vec<_> = visible
+            .iter()
+            .filter_map(|ev| Some((*ev, ev.suspicion?)))
+            .collect();
+        ui.label(format!("{} suspicious events", suspicious.len()));
+        ui.separator();
+        for &(ev, suspicion) in suspicious.iter().take(MAX_SUSPICIOUS_SHOWN) {
+            let text = RichText::new(format!("{} {}: {suspicion}", ev.kind, ev.id))
+                .color(self.palette.color(&ev.kind));
+            if ui.selectable_label(self.is_selected(ev), text).clicked() {
+                self.selected = Some((ev.kind.clone(), ev.id));
+            }
+        }
+        if suspicious.len() > MAX_SUSPICIOUS_SHOWN {
+            ui.label(format!(
+                "…and {} more",
+                suspicious.len() - MAX_SUSPICIOUS_SHOWN
+            ));
+        }
+    }
+
+    /// the statistics of the visible events, only recomputed once the events or filters change
+    fn stats(&mut self, events: &Arc<Events>) -> &StatsCache {
+        let fresh = self.stats_cache.as_ref().is_some_and(|cache| {
+            cache.events.ptr_eq(&Arc::downgrade(events))
+                && cache.view_by == self.view_by
+                && cache.id == self.id
+                && cache.from == self.from
+                && cache.to == self.to
+                && cache.min_duration == self.min_duration
+                && cache.max_duration == self.max_duration
+                && cache.show_orphans == self.show_orphans
+                && cache.hidden_labels == self.hidden_labels
+        });
+        let cache = match self.stats_cache.take() {
+            Some(cache) if fresh => cache,
+            _ => self.compute_stats(events),
+        };
+        self.stats_cache.insert(cache)
+    }
+
+    fn compute_stats(&self, events: &Arc<Events>) -> StatsCache {
+        let labels = events
+            .labels
+            .iter()
+            .filter_map(|label| {
+                let visible: Vec<_> = events
+                    .events
+                    .iter()
+                    .filter(|ev| &ev.kind == label && self.visible(ev))
+                    .collect();
+                if visible.is_empty() {
+                    return None;
+                }
+                Some(LabelStats::new(label.clone(), &visible))
+            })
+            .collect();
+        let visible: Vec<_> = self.visible_events(events).collect();
+        let ids: HashSet<_> = visible.iter().map(|ev| ev.id).collect();
+        let span = visible.first().map(|first| {
+            let end = visible.iter().map(|ev| ev.span.end()).fold(0.0, f64::max);
+            (first.span.start, end)
+        });
+        let concurrency = concurrency(
+            visible
+                .iter()
+                .filter(|ev| !ev.mark)
+                .map(|ev| (ev.span.start, ev.span.end())),
+        );
+        StatsCache {
+            events: Arc::downgrade(events),
+            view_by: self.view_by.clone(),
+            id: self.id,
+            from: self.from,
+            to: self.to,
+            min_duration: self.min_duration,
+            max_duration: self.max_duration,
+            show_orphans: self.show_orphans,
+            hidden_labels: self.hidden_labels.clone(),
+            labels,
+            total: LabelStats::new("all".to_owned(), &visible),
+            ids: ids.len(),
+            span,
+            gaps: idle_gaps(&concurrency),
+            concurrency,
+        }
+    }
+
+    /// loads a file dropped onto the window, and shows an overlay while one is dragged over it
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        use egui::{Align2, Id, LayerId, Order, TextStyle};
+
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter =
+                ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("file_drop_target")));
+            let screen_rect = ctx.screen_rect();
+            painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(192));
+            painter.text(
+                screen_rect.center(),
+                Align2::CENTER_CENTER,
+                "Drop the file to load it",
+                TextStyle::Heading.resolve(&ctx.style()),
+                Color32::WHITE,
+            );
+        }
+
+        let Some(file) = ctx.input_mut(|i| i.raw.dropped_files.pop()) else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = file.path {
+            self.filename = path.display().to_string();
+            self.read(ctx);
+            return;
+        }
+        if let Some(bytes) = file.bytes {
+            self.loads.fetch_add(1, Ordering::Relaxed);
+            let (events, errors) = Events::read_bytes(&bytes, self.read_options());
+            *self.events.lock() = Arc::new(events.with_source(file.name));
+            *self.errors.lock() = errors;
+            *self.loaded_from.lock() = None;
+        }
+    }
+
+    /// Ctrl+R and F5 reload the file, unless some text is being edited
+    #[cfg(not(target_arch = "wasm32"))]
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        use egui::{Key, Modifiers};
+
+        if ctx.wants_keyboard_input() || self.filename == "-" {
+            return;
+        }
+        let reload = ctx.input_mut(|i| {
+            i.consume_key(Modifiers::COMMAND, Key::R) || i.consume_key(Modifiers::NONE, Key::F5)
+        });
+        if reload {
+            self.read(ctx);
+        }
+    }
+
+    fn time_format(&self) -> TimeFormat {
+        TimeFormat {
+            unit: self.unit,
+            decimals: self.decimals,
+        }
+    }
+
+    /// what to add to the relative times of `events` that aren't those of a single event, like
+    /// the axis. Files that started at different times have no absolute time in common, so
+    /// those stay relative
+    fn time_offset(&self, events: &Events) -> f64 {
+        let shared = events.offsets.windows(2).all(|w| w[0] == w[1]);
+        if self.absolute && shared {
+            events.offset_of(0)
+        } else {
+            0.0
+        }
+    }
+
+    /// what to add to the relative times of `ev` when displaying them
+    fn event_offset(&self, events: &Events, ev: &Event) -> f64 {
+        if self.absolute {
+            events.offset_of(ev.source)
+        } else {
+            0.0
+        }
+    }
+
+    /// whether `ev` passes all filters
+    fn visible(&self, ev: &Event) -> bool {
+        self.view_by.matching(ev)
+            && self.id.is_none_or(|id| ev.id == id)
+            && ev.span.overlaps(self.from, self.to)
+            && (self.show_orphans || !ev.orphan)
+            && !self.hidden_labels.contains(&ev.kind)
+            && self.within_durations(ev)
+    }
+
+    /// sets [`Analyzer::id`] from what was typed into [`Analyzer::id_filter`], anything that
+    /// isn't an id shows all of them
+    fn parse_id_filter(&mut self) {
+        self.id = self.id_filter.trim().parse().ok();
+    }
+
+    /// the events of `events` that pass all filters, by start
+    fn visible_events<'a>(&'a self, events: &'a Events) -> impl Iterator<Item = &'a Event> {
+        events.events.iter().filter(|ev| self.visible(ev))
+    }
+
+    /// whether anything is hidden or highlighted, see [`Analyzer::reset_filters`]
+    fn filtered(&self) -> bool {
+        self.view_by != ViewBy::Any
+            || !self.regex.is_empty()
+            || !self.search.is_empty()
+            || !self.id_filter.is_empty()
+            || self.from.is_some()
+            || self.to.is_some()
+            || self.min_duration.is_some()
+            || self.max_duration.is_some()
+            || !self.hidden_labels.is_empty()
+    }
+
+    fn reset_filters(&mut self) {
+        self.view_by = ViewBy::Any;
+        self.regex.clear();
+        self.regex_error = None;
+        self.search.clear();
+        self.id_filter.clear();
+        self.id = None;
+        self.from = None;
+        self.to = None;
+        self.min_duration = None;
+        self.max_duration = None;
+        self.hidden_labels.clear();
+    }
+
+    /// events without a real duration only pass while no duration is filtered by
+    fn within_durations(&self, ev: &Event) -> bool {
+        if self.min_duration.is_none() && self.max_duration.is_none() {
+            return true;
+        }
+        ev.complete()
+            && self.min_duration.is_none_or(|min| ev.span.duration >= min)
+            && self.max_duration.is_none_or(|max| ev.span.duration <= max)
+    }
+
+    /// the settings of the last session, if there was one
+    fn restore(storage: Option<&dyn eframe::Storage>) -> Self {
+        let Some(storage) = storage else {
+            return Self::new();
+        };
+        let mut analyzer: Self = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        // the drag value never goes below 1, but the stored state might have been edited
+        analyzer.bins = analyzer.bins.max(1);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut recent: Vec<String> =
+                eframe::get_value(storage, RECENT_FILES_KEY).unwrap_or_default();
+            recent.retain(|path| std::path::Path::new(path).exists());
+            *analyzer.recent.lock() = recent;
+        }
+        analyzer
+    }
+
+    fn new() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            filename: String::new(),
+            #[cfg(target_arch = "wasm32")]
+            url: String::new(),
+            events: Arc::new(Mutex::new(Arc::new(Events::default()))),
+            view_by: ViewBy::Any,
+            regex: String::new(),
+            regex_error: None,
+            search: String::new(),
+            id_filter: String::new(),
+            id: None,
+            view: View::Timeline,
+            bins: 20,
+            rows: Rows::Id,
+            row_order: RowOrder::Sorted,
+            open_spans: OpenSpans::FixedDuration,
+            show_orphans: false,
+            hidden_labels: HashSet::new(),
+            open_duration: 1000.0,
+            separators: Separators::default(),
+            fold_case: false,
+            from: None,
+            to: None,
+            min_duration: None,
+            max_duration: None,
+            absolute: false,
+            unit: TimeUnit::Raw,
+            decimals: 3,
+            plot_rect: None,
+            viewport: None,
+            selected: None,
+            fit: false,
+            zoom_to: None,
+            context_event: None,
+            show_stats: false,
+            show_health: false,
+            show_concurrency: false,
+            show_gaps: false,
+            min_bar_width: true,
+            merge_gap: None,
+            crosshair: false,
+            palette: Palette::Hues,
+            bar_height: 1.0,
+            max_bars: 100_000,
+            aspect: 10.0,
+            auto_aspect: false,
+            cursor: None,
+            playing: false,
+            play_seconds: 10.0,
+            dark_mode: true,
+            stats_cache: None,
+            loaded: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            export_png: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            watch: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            watcher: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            recent: Arc::new(Mutex::new(Vec::new())),
+            #[cfg(not(target_arch = "wasm32"))]
+            max_file_mib: 1024,
+            #[cfg(not(target_arch = "wasm32"))]
+            too_large: Arc::new(Mutex::new(None)),
+            errors: Arc::new(Mutex::new(Vec::new())),
+            loading: Arc::new(AtomicBool::new(false)),
+            loads: Arc::new(AtomicUsize::new(0)),
+            last_read: Arc::new(Mutex::new(None)),
+            loaded_from: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// how many of the `(start, end)` spans are active over time, as the count from each time on.
+/// Spans that only touch don't overlap, and those that take no time are never active.
+fn concurrency(spans: impl Iterator<Item = (f64, f64)>) -> Vec<(f64, usize)> {
+    // their end would be counted before their start
+    let mut boundaries: Vec<_> = spans
+        .filter(|(start, end)| start < end)
+        .flat_map(|(start, end)| [(start, true), (end, false)])
+        .collect();
+    // ends first, so that touching spans don't count as overlapping
+    boundaries.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    let mut steps: Vec<(f64, usize)> = Vec::new();
+    let mut active = 0_usize;
+    for (time, starts) in boundaries {
+        if starts {
+            active += 1;
+        } else {
+            active -= 1;
+        }
+        match steps.last_mut() {
+            Some(last) if last.0 == time => last.1 = active,
+            _ => steps.push((time, active)),
+        }
+    }
+    steps
+}
+
+/// joins the `shown` events of each label and `row` that start at most `gap` after the previous
+/// one ended, as the index of the first in `shown`, where the last ends and how many were
+/// joined. Marks and open events are never joined
+fn merge_gaps<'a>(
+    shown: &[(&'a Event, f64)],
+    gap: f64,
+    row: impl Fn(&Event) -> u32,
+) -> Vec<(usize, f64, usize)> {
+    let mut merged: Vec<(usize, f64, usize)> = Vec::new();
+    // the bar in `merged` that each label is extending in each row
+    let mut current = HashMap::<(&'a str, u32), usize>::new();
+    for (i, &(ev, end)) in shown.iter().enumerate() {
+        if ev.mark || ev.open {
+            merged.push((i, end, 1));
+            continue;
+        }
+        let key = (ev.kind.as_str(), row(ev));
+        if let Some(&bar) = current.get(&key) {
+            let (_, bar_end, count) = &mut merged[bar];
+            if ev.span.start <= *bar_end + gap {
+                *bar_end = bar_end.max(end);
+                *count += 1;
+                continue;
+            }
+        }
+        current.insert(key, merged.len());
+        merged.push((i, end, 1));
+    }
+    merged
+}
+
+/// merges the overlapping `shown` events in each of their `rows`, as
+/// `(label, row, start, end, count)` where `count` is how many events were merged. Marks are
+/// left out, they have no duration
+fn union_spans<'a>(
+    shown: &[(&'a Event, f64)],
+    rows: &[usize],
+) -> Vec<(&'a str, usize, f64, f64, usize)> {
+    let mut spans: Vec<_> = shown
+        .iter()
+        .zip(rows)
+        .filter(|((ev, _), _)| !ev.mark)
+        .map(|(&(ev, end), &row)| (ev.kind.as_str(), row, ev.span.start, end))
+        .collect();
+    spans.sort_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)));
+
+    let mut union: Vec<(&str, usize, f64, f64, usize)> = Vec::new();
+    for (label, row, start, end) in spans {
+        match union.last_mut() {
+            Some(last) if last.1 == row && start <= last.3 => {
+                last.3 = last.3.max(end);
+                last.4 += 1;
+            }
+            _ => union.push((label, row, start, end, 1)),
+        }
+    }
+    union
+}
+
+/// the `(start, end)` of every time span in `concurrency` in which no event is active, longest
+/// first. The time before the first and after the last event does not count
+fn idle_gaps(concurrency: &[(f64, usize)]) -> Vec<(f64, f64)> {
+    let mut gaps: Vec<_> = concurrency
+        .windows(2)
+        .filter(|w| w[0].1 == 0)
+        .map(|w| (w[0].0, w[1].0))
+        .collect();
+    gaps.sort_by(|a, b| (b.1 - b.0).total_cmp(&(a.1 - a.0)));
+    gaps
+}
+
+/// the highest count of `concurrency` along with the first time span it was reached in
+fn peak_concurrency(concurrency: &[(f64, usize)]) -> Option<(usize, f64, f64)> {
+    concurrency
+        .windows(2)
+        .map(|w| (w[0].1, w[0].0, w[1].0))
+        .filter(|&(count, _, _)| count > 0)
+        .reduce(|max, step| if step.0 > max.0 { step } else { max })
+}
+
+/// greedily packs the events of each label into as few lanes as possible, so that no two
+/// overlap. Returns the row of each `(event, end)`, with the lanes of each label below the
+/// lanes of the previous one.
+fn pack_lanes(events: &[(&Event, f64)], labels: &[String]) -> Vec<usize> {
+    // the end of the last event in each lane, per label
+    let mut lane_ends = HashMap::<&str, Vec<f64>>::new();
+    let lanes: Vec<_> = events
+        .iter()
+        .map(|&(ev, end)| {
+            let ends = lane_ends.entry(ev.kind.as_str()).or_default();
+            match ends.iter().position(|&e| e <= ev.span.start) {
+                Some(lane) => {
+                    ends[lane] = end;
+                    lane
+                }
+                None => {
+                    ends.push(end);
+                    ends.len() - 1
+                }
+            }
+        })
+        .collect();
+
+    let mut first_lane = HashMap::new();
+    let mut next = 0;
+    for label in labels {
+        first_lane.insert(label.as_str(), next);
+        next += lane_ends.get(label.as_str()).map_or(0, Vec::len);
+    }
+
+    events
+        .iter()
+        .zip(lanes)
+        .map(|(&(ev, _), lane)| first_lane[ev.kind.as_str()] + lane)
+        .collect()
+}
+
+/// numbers the `rows` from 0 in the order they first appear. With `groups`, the rows of a
+/// group are kept together in their original order, and the groups are ordered instead
+fn first_seen_rows(rows: &[usize], groups: &[&str]) -> Vec<usize> {
+    let mut group_order = HashMap::new();
+    // what each row is sorted by
+    let mut keys = HashMap::new();
+    for (i, &row) in rows.iter().enumerate() {
+        let key = match groups.get(i) {
+            Some(group) => {
+                let next = group_order.len();
+                (*group_order.entry(*group).or_insert(next), row)
+            }
+            None => (keys.len(), row),
+        };
+        keys.entry(row).or_insert(key);
+    }
+    let mut order: Vec<_> = keys.into_iter().map(|(row, key)| (key, row)).collect();
+    order.sort_unstable();
+    let renumbered: HashMap<_, _> = order
+        .into_iter()
+        .enumerate()
+        .map(|(new, (_, row))| (row, new))
+        .collect();
+    rows.iter().map(|row| renumbered[row]).collect()
+}
+
+/// reads every file in `dir` that the file dialog would offer, in the order of their names,
+/// as sources of their own
+#[cfg(not(target_arch = "wasm32"))]
+fn read_dir(dir: &std::path::Path, options: ReadOptions) -> (Events, Vec<Error>) {
+    const EXTENSIONS: [&str; 3] = ["jfon", "json", "gz"];
+
+    let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|ext| EXTENSIONS.iter().any(|e| ext == *e))
+            })
+            .collect(),
+        Err(e) => return (Events::default(), vec![Error::IoError(e)]),
+    };
+    paths.sort();
+
+    let mut events = Events::default();
+    let mut errors = Vec::new();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        match std::fs::read(&path) {
+            Ok(data) => {
+                let (read, errs) = Events::read_bytes(&data, options);
+                events.append(read.with_source(name.into_owned()));
+                errors.extend(errs);
+            }
+            Err(e) => errors.push(Error::IoError(e)),
+        }
+    }
+    (events, errors)
+}
+
+/// moves `path` to the front of the recently opened files
+#[cfg(not(target_arch = "wasm32"))]
+fn remember_file(recent: &Mutex<Vec<String>>, path: &std::path::Path) {
+    const MAX_RECENT: usize = 10;
+
+    let path = std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_owned())
+        .display()
+        .to_string();
+    let mut recent = recent.lock();
+    recent.retain(|p| *p != path);
+    recent.insert(0, path);
+    recent.truncate(MAX_RECENT);
+}
+
+/// a checkbox that enables editing `value`, which starts out as `default` when checked
+/// `value` is relative, but edited as `value + offset`
+fn optional_value(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<f64>,
+    default: f64,
+    offset: f64,
+) {
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *value = enabled.then_some(default);
+    }
+    if let Some(value) = value {
+        let mut shown = *value + offset;
+        if ui
+            .add(egui::DragValue::new(&mut shown).clamp_range(offset..=f64::MAX))
+            .changed()
+        {
+            *value = shown - offset;
+        }
+    }
+}
+
+/// like [`optional_value`], but for a duration edited in `unit`
+fn optional_duration(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<f64>,
+    default: f64,
+    unit: TimeUnit,
+) {
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *value = enabled.then_some(default);
+    }
+    if let Some(value) = value {
+        let nanos = unit.nanos();
+        let mut shown = *value / nanos;
+        let suffix = match unit {
+            TimeUnit::Raw => String::new(),
+            unit => format!(" {}", unit.suffix()),
+        };
+        if ui
+            .add(
+                egui::DragValue::new(&mut shown)
+                    .clamp_range(0.0..=f64::MAX)
+                    .suffix(suffix),
+            )
+            .changed()
+        {
+            *value = shown * nanos;
+        }
+    }
+}
+
+/// whether `filename` should be downloaded rather than read from disk
+fn is_url(filename: &str) -> bool {
+    filename.starts_with("http://") || filename.starts_with("https://")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url).call().map_err(|e| e.to_string())?;
+    let mut data = Vec::new();
+    std::io::Read::read_to_end(&mut response.into_reader(), &mut data)
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn execute<F: Future<Output = ()> + Send + 'static>(f: F) {
+    // this is stupid... use any executor of your choice instead
+
+    std::thread::spawn(move || futures::executor::block_on(f));
+}
+#[cfg(target_arch = "wasm32")]
+fn execute<F: Future<Output = ()> + 'static>(f: F) {
+    wasm_bindgen_futures::spawn_local(f);
+}
+
+/// asks the user where to save `data`, reporting failures in `errors`
+#[cfg(not(target_arch = "wasm32"))]
+fn save_file(file_name: &str, extension: &str, data: Vec<u8>, errors: Arc<Mutex<Vec<Error>>>) {
+    let task = rfd::AsyncFileDialog::new()
+        .add_filter(extension, &[extension])
+        .set_file_name(file_name)
+        .save_file();
+    execute(async move {
+        if let Some(file) = task.await {
+            log::info!("saving {:?}", file.path());
+            if let Err(e) = std::fs::write(file.path(), data) {
+                errors.lock().push(Error::IoError(e));
+            }
+        }
+    });
+}
+
+/// offers `data` as a download, reporting failures in `errors`
+#[cfg(target_arch = "wasm32")]
+fn save_file(file_name: &str, _extension: &str, data: Vec<u8>, errors: Arc<Mutex<Vec<Error>>>) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let download = || -> Result<(), JsValue> {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data.as_slice()));
+        let blob = web_sys::Blob::new_with_u8_array_sequence(&parts)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or("no document")?;
+        let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+        web_sys::Url::revoke_object_url(&url)
+    };
+    if let Err(e) = download() {
+        let e = std::io::Error::other(format!("download failed: {e:?}"));
+        errors.lock().push(Error::IoError(e));
+    }
+}
+
+/// rounds `number` to `decimals` digits after the point
+fn round(number: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals.min(15) as i32);
+    (number * scale).round() / scale
+}
+
+/// writes `events` as CSV with the columns `kind,id,start,duration,end`.
+/// `offset` of an event is added to its times, just like in the plot, and they are rounded to
+/// `decimals`. Times that weren't recorded are left empty, rather than the placeholders drawn
+/// for open and orphan events.
+fn write_csv<'a>(
+    events: impl IntoIterator<Item = &'a Event>,
+    offset: impl Fn(&Event) -> f64,
+    decimals: usize,
+) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["kind", "id", "start", "duration", "end"])?;
+    for ev in events {
+        let offset = offset(ev);
+        let time = |time: f64, recorded: bool| {
+            if recorded {
+                round(time, decimals).to_string()
+            } else {
+                String::new()
+            }
+        };
+        writer.write_record([
+            ev.kind.clone(),
+            ev.id.to_string(),
+            time(ev.span.start + offset, !ev.orphan),
+            time(ev.span.duration, !ev.open && !ev.orphan),
+            time(ev.span.end() + offset, !ev.open),
+        ])?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+/// writes `events` in the Chrome trace event format, which Perfetto and `chrome://tracing` can
+/// open. Times are taken to be in nanoseconds and converted to the microseconds Chrome expects.
+/// `offset` of an event is added to its times, just like in the plot, and they are rounded to
+/// `decimals`.
+fn write_trace<'a>(
+    events: impl IntoIterator<Item = &'a Event>,
+    offset: impl Fn(&Event) -> f64,
+    decimals: usize,
+) -> Result<Vec<u8>, serde_json::Error> {
+    use serde_json::json;
+
+    let us = |time: f64| round(time / TimeUnit::Us.nanos(), decimals);
+    let trace: Vec<_> = events
+        .into_iter()
+        .map(|ev| {
+            let mut event = json!({
+                "name": ev.kind,
+                "cat": ev.kind,
+                "pid": 1,
+                "tid": ev.thread.unwrap_or(ev.id),
+                "ts": us(ev.span.start + offset(ev)),
+            });
+            // a begin event without an end lasts until the end of the trace
+            if ev.mark {
+                event["ph"] = json!("i");
+                event["s"] = json!("t");
+            } else if ev.open {
+                event["ph"] = json!("B");
+            } else {
+                event["ph"] = json!("X");
+                event["dur"] = json!(us(ev.span.duration));
+            }
+            event
+        })
+        .collect();
+    serde_json::to_vec(&json!({ "traceEvents": trace }))
+}
+
+/// encodes tightly packed RGBA pixels, the first row being the top one
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(pixels)?;
+    Ok(out)
+}
+
+/// how many errors [`show_errors`] displays before summarizing the rest
+const MAX_ERRORS_SHOWN: usize = 100;
+
+fn show_errors(errors: &[Error], unlisted: Unlisted, ui: &mut egui::Ui) {
+    egui::ScrollArea::vertical()
+        .max_height(150.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            for error in errors.iter().take(MAX_ERRORS_SHOWN) {
+                show_error(error, ui);
+            }
+            let more =
+                errors.len().saturating_sub(MAX_ERRORS_SHOWN) + unlisted.errors + unlisted.warnings;
+            if more > 0 {
+                ui.label(format!("…and {more} more"));
+            }
+        });
+}
+
+fn show_error(error: &Error, ui: &mut egui::Ui) {
+    let Error::FormatError { line_number, kind } = error else {
+        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+        return;
+    };
+    let (prefix, color) = if kind.is_warning() {
+        ("Warning on Line", ui.visuals().warn_fg_color)
+    } else {
+        ("Error on Line", ui.visuals().error_fg_color)
+    };
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(prefix).color(color));
+        ui.label(
+            RichText::new(format!("{line_number}"))
+                .color(color)
+                .monospace(),
+        );
+        if let Some(column) = kind.column() {
+            ui.label(RichText::new(", column").color(color));
+            ui.label(RichText::new(format!("{column}")).color(color).monospace());
+        }
+        ui.label(RichText::new(":").color(color));
+        ui.label(RichText::new(kind.to_string()).color(color));
+    });
+}
+
+impl eframe::App for Analyzer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.style().visuals.dark_mode != self.dark_mode {
+            ctx.set_visuals(if self.dark_mode {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+        self.handle_dropped_files(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.handle_shortcuts(ctx);
+        // locked only once, so that the whole frame shows the same events and the loader is
+        // never blocked for long
+        let events = Arc::clone(&self.events.lock());
+        self.validate_selection(&events);
+        if !self
+            .loaded
+            .as_ref()
+            .is_some_and(|(loaded, _)| loaded.ptr_eq(&Arc::downgrade(&events)))
+        {
+            self.loaded = Some((Arc::downgrade(&events), ctx.input(|i| i.time)));
+        }
+        // applied once the snapshot is dropped, so that the events are not copied
+        let mut open_duration_changed = false;
+
+        egui::SidePanel::right("stats_panel").show_animated(ctx, self.show_stats, |ui| {
+            ui.heading("Statistics");
+            egui::ScrollArea::vertical().show(ui, |ui| self.show_stats(ui, &events));
+        });
+
+        egui::SidePanel::right("health_panel").show_animated(ctx, self.show_health, |ui| {
+            ui.heading("Data health");
+            egui::ScrollArea::vertical().show(ui, |ui| self.show_health(ui, &events));
+        });
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| self.show_status(ui, &events));
+
+        egui::TopBottomPanel::bottom("details_panel").show_animated(
+            ctx,
+            self.selected.is_some(),
+            |ui| self.show_details(ui, &events),
+        );
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("JFON viewer");
+                let (icon, hover) = if self.dark_mode {
+                    ("☀", "switch to the light theme")
+                } else {
+                    ("🌙", "switch to the dark theme")
+                };
+                if ui.button(icon).on_hover_text(hover).clicked() {
+                    self.dark_mode = !self.dark_mode;
+                }
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.horizontal(|ui| {
+                use eframe::egui::Key;
+                let name_label = ui.label("filename: ");
+                let input = ui
+                    .text_edit_singleline(&mut self.filename)
+                    .labelled_by(name_label.id);
+                if input.lost_focus() && input.ctx.input(|r| r.key_down(Key::Enter)) {
+                    self.read(ctx)
+                }
+                ui.menu_button("Recent", |ui| {
+                    self.recent
+                        .lock()
+                        .retain(|path| std::path::Path::new(path).exists());
+                    let recent = self.recent.lock().clone();
+                    if recent.is_empty() {
+                        ui.label("no recent files");
+                    }
+                    for path in recent {
+                        if ui.button(&path).clicked() {
+                            self.filename = path;
+                            self.read(ctx);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.checkbox(&mut self.watch, "Watch file");
+                self.poll_watcher(ctx);
+            });
+
+            #[cfg(target_arch = "wasm32")]
+            ui.horizontal(|ui| {
+                use eframe::egui::Key;
+                let url_label = ui.label("URL: ");
+                let input = ui
+                    .text_edit_singleline(&mut self.url)
+                    .labelled_by(url_label.id);
+                let enter = input.lost_focus() && input.ctx.input(|r| r.key_down(Key::Enter));
+                if (enter || ui.button("load").clicked()) && is_url(&self.url) {
+                    self.fetch_url(&self.url, ctx);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    // stdin can only be read once
+                    let reloadable = self.filename != "-";
+                    if ui
+                        .add_enabled(reloadable, egui::Button::new("reload"))
+                        .on_hover_text("Ctrl+R or F5")
+                        .clicked()
+                    {
+                        self.read(ctx);
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    if ui.button("Export PNG").clicked() {
+                        self.export_png = true;
+                    }
+                }
+                if ui.button("Export CSV").clicked() {
+                    let visible = self.visible_events(&events);
+                    match write_csv(visible, |ev| self.event_offset(&events, ev), self.decimals) {
+                        Ok(csv) => save_file("events.csv", "csv", csv, Arc::clone(&self.errors)),
+                        Err(e) => {
+                            let e = std::io::Error::other(e);
+                            self.errors.lock().push(Error::IoError(e));
+                        }
+                    }
+                }
+                if ui.button("Export trace").clicked() {
+                    let visible = self.visible_events(&events);
+                    match write_trace(visible, |ev| self.event_offset(&events, ev), self.decimals) {
+                        Ok(trace) => {
+                            save_file("trace.json", "json", trace, Arc::clone(&self.errors))
+                        }
+                        Err(e) => {
+                            let e = std::io::Error::other(e);
+                            self.errors.lock().push(Error::IoError(e));
+                        }
+                    }
+                }
+                if ui
+                    .add_enabled(
+                        !events.sources.is_empty(),
+                        egui::Button::new("Save session"),
+                    )
+                    .on_hover_text("save all events to open them again without parsing")
+                    .clicked()
+                {
+                    match events.write_session() {
+                        Ok(session) => {
+                            save_file("session.json", "json", session, Arc::clone(&self.errors))
+                        }
+                        Err(e) => {
+                            let e = std::io::Error::other(e);
+                            self.errors.lock().push(Error::IoError(e));
+                        }
+                    }
+                }
+                ui.toggle_value(&mut self.show_stats, "Statistics");
+                ui.toggle_value(&mut self.show_health, "Health")
+                    .on_hover_text("list events that might have been measured wrongly");
+                if ui.button("Open file…").clicked() {
+                    self.pick_file(ctx, false);
+                }
+                if ui
+                    .button("Add file…")
+                    .on_hover_text("load another file next to the current one, to compare them")
+                    .clicked()
+                {
+                    self.pick_file(ctx, true);
+                }
+                if ui
+                    .add_enabled(!events.sources.is_empty(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    self.loads.fetch_add(1, Ordering::Relaxed);
+                    *self.events.lock() = Arc::default();
+                    self.errors.lock().clear();
+                    *self.last_read.lock() = None;
+                    *self.loaded_from.lock() = None;
+                }
+                // only what came from the file name is read again, anything else keeps its events
+                // and the format applies to the next file
+                #[cfg(not(target_arch = "wasm32"))]
+                let format = (self.separators, self.fold_case);
+                ui.menu_button("Format…", |ui| self.show_format(ui));
+                #[cfg(not(target_arch = "wasm32"))]
+                if (self.separators, self.fold_case) != format
+                    && self.loaded_from.lock().as_ref() == Some(&self.filename)
+                {
+                    self.read(ctx);
+                }
+
+                ui.separator();
+                let search_label = ui.label("Search: ");
+                ui.text_edit_singleline(&mut self.search)
+                    .labelled_by(search_label.id);
+                let id_label = ui.label("Id: ");
+                if ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.id_filter)
+                            .hint_text("any")
+                            .desired_width(60.0),
+                    )
+                    .labelled_by(id_label.id)
+                    .changed()
+                {
+                    self.parse_id_filter();
+                }
+                if ui
+                    .add_enabled(self.filtered(), egui::Button::new("Reset filters"))
+                    .on_hover_text("show every event again, the loaded files stay")
+                    .clicked()
+                {
+                    self.reset_filters();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("View By: ");
+                // sources are shown by name rather than index
+                let source_name = |i: usize| match events.sources.get(i) {
+                    Some(name) => format!("Source: {name}"),
+                    None => ViewBy::Source(i).to_string(),
+                };
+                let selected = match self.view_by {
+                    ViewBy::Source(i) => source_name(i),
+                    ref view_by => view_by.to_string(),
+                };
+                ComboBox::from_label("")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.view_by, ViewBy::Any, "Any");
+
+                        for label in &events.labels {
+                            let val = ViewBy::Label(label.clone());
+                            let s = val.to_string();
+                            ui.selectable_value(&mut self.view_by, val, s);
+                        }
+                        if events.sources.len() > 1 {
+                            for i in 0..events.sources.len() {
+                                ui.selectable_value(
+                                    &mut self.view_by,
+                                    ViewBy::Source(i),
+                                    source_name(i),
+                                );
+                            }
+                        }
+                    });
+                ui.menu_button("Select…", |ui| {
+                    let labels = &events.labels;
+                    let mut selected = match &self.view_by {
+                        ViewBy::Any | ViewBy::Regex(_) | ViewBy::Source(_) => {
+                            labels.iter().cloned().collect()
+                        }
+                        ViewBy::Label(l) => HashSet::from([l.clone()]),
+                        ViewBy::Labels(ls) => ls.clone(),
+                    };
+                    let mut changed = false;
+                    for label in labels {
+                        let mut checked = selected.contains(label);
+                        if ui.checkbox(&mut checked, label).changed() {
+                            changed = true;
+                            if checked {
+                                selected.insert(label.clone());
+                            } else {
+                                selected.remove(label);
+                            }
+                        }
+                    }
+                    if changed {
+                        self.view_by = if labels.iter().all(|l| selected.contains(l)) {
+                            ViewBy::Any
+                        } else {
+                            ViewBy::Labels(selected)
+                        };
+                    }
+                });
+
+                let regex_label = ui.label("Regex: ");
+                let input = ui
+                    .text_edit_singleline(&mut self.regex)
+                    .labelled_by(regex_label.id);
+                if input.lost_focus() && input.ctx.input(|r| r.key_down(egui::Key::Enter)) {
+                    self.regex_error = None;
+                    if self.regex.is_empty() {
+                        self.view_by = ViewBy::Any;
+                    } else {
+                        match Pattern::new(&self.regex) {
+                            Ok(p) => self.view_by = ViewBy::Regex(p),
+                            Err(e) => self.regex_error = Some(e),
+                        }
+                    }
+                }
+                if let Some(e) = &self.regex_error {
+                    ui.colored_label(ui.visuals().error_fg_color, "invalid regex")
+                        .on_hover_text(e.to_string());
+                }
+
+                ComboBox::from_id_source("view")
+                    .selected_text(self.view.to_string())
+                    .show_ui(ui, |ui| {
+                        for val in [View::Timeline, View::Histogram, View::BoxPlot] {
+                            ui.selectable_value(&mut self.view, val, val.to_string());
+                        }
+                    });
+                match self.view {
+                    View::Timeline => {
+                        ui.label("Rows: ");
+                        ComboBox::from_id_source("rows")
+                            .selected_text(self.rows.to_string())
+                            .show_ui(ui, |ui| {
+                                for val in Rows::ALL {
+                                    ui.selectable_value(&mut self.rows, val, val.to_string());
+                                }
+                            });
+                        ComboBox::from_id_source("row_order")
+                            .selected_text(self.row_order.to_string())
+                            .show_ui(ui, |ui| {
+                                for val in [RowOrder::Sorted, RowOrder::FirstSeen] {
+                                    ui.selectable_value(&mut self.row_order, val, val.to_string());
+                                }
+                            });
+                        ui.checkbox(&mut self.show_concurrency, "Concurrency")
+                            .on_hover_text("draw how many events are active at once");
+                        ui.checkbox(&mut self.min_bar_width, "Min width")
+                            .on_hover_text(format!(
+                                "draw bars at least {MIN_BAR_PIXELS} pixels wide, however short"
+                            ));
+                        if self.rows != Rows::Union {
+                            optional_duration(
+                                ui,
+                                "Merge gaps ≤",
+                                &mut self.merge_gap,
+                                0.0,
+                                self.unit,
+                            );
+                        }
+                        ui.add(
+                            egui::Slider::new(&mut self.bar_height, 0.1..=1.0).text("bar height"),
+                        )
+                        .on_hover_text("how much of its row a bar fills");
+                        ui.add(egui::DragValue::new(&mut self.max_bars).prefix("max bars: "))
+                            .on_hover_text(
+                                "draw no more bars than this, the earliest first. 0 draws all",
+                            );
+                        ui.checkbox(&mut self.crosshair, "Crosshair")
+                            .on_hover_text("list every event active at the hovered time");
+                        ui.checkbox(&mut self.auto_aspect, "Auto aspect")
+                            .on_hover_text("stretch the events to fill the plot");
+                        ui.add_enabled(
+                            !self.auto_aspect,
+                            egui::Slider::new(&mut self.aspect, 0.001..=1e6)
+                                .logarithmic(true)
+                                .text("aspect"),
+                        )
+                        .on_hover_text("how much wider than high a unit of time is drawn");
+                        ui.checkbox(&mut self.show_gaps, "Gaps")
+                            .on_hover_text(format!(
+                            "shade the {LONGEST_GAPS} longest times in which no event is active"
+                        ));
+                        if ui
+                            .button("Fit")
+                            .on_hover_text("show all visible events")
+                            .clicked()
+                        {
+                            self.fit = true;
+                        }
+                        ui.separator();
+                        let play = if self.playing { "⏸" } else { "▶" };
+                        if ui
+                            .button(play)
+                            .on_hover_text(
+                                "sweep a cursor across the events, dimming those ahead of it",
+                            )
+                            .clicked()
+                        {
+                            self.playing = !self.playing;
+                        }
+                        if ui
+                            .add_enabled(self.cursor.is_some(), egui::Button::new("⏹"))
+                            .on_hover_text("stop and show all events")
+                            .clicked()
+                        {
+                            self.cursor = None;
+                            self.playing = false;
+                        }
+                        ui.add(
+                            egui::DragValue::new(&mut self.play_seconds)
+                                .clamp_range(1.0..=600.0)
+                                .suffix(" s"),
+                        )
+                        .on_hover_text("how long sweeping across all events takes");
+                    }
+                    View::Histogram => {
+                        ui.label("Bins: ");
+                        ui.add(egui::DragValue::new(&mut self.bins).clamp_range(1..=1000));
+                    }
+                    View::BoxPlot => {}
+                }
+
+                ui.label("Open events: ");
+                ComboBox::from_id_source("open_spans")
+                    .selected_text(self.open_spans.to_string())
+                    .show_ui(ui, |ui| {
+                        for val in [
+                            OpenSpans::FixedDuration,
+                            OpenSpans::ExtendToEnd,
+                            OpenSpans::GrowToNow,
+                            OpenSpans::Hide,
+                        ] {
+                            ui.selectable_value(&mut self.open_spans, val, val.to_string());
+                        }
+                    });
+                if self.open_spans == OpenSpans::FixedDuration {
+                    // edited in the display unit
+                    let nanos = self.unit.nanos();
+                    let mut duration = self.open_duration / nanos;
+                    let suffix = match self.unit {
+                        TimeUnit::Raw => String::new(),
+                        unit => format!(" {}", unit.suffix()),
+                    };
+                    let input = ui
+                        .add(
+                            egui::DragValue::new(&mut duration)
+                                .clamp_range(0.0..=f64::MAX)
+                                .suffix(suffix),
+                        )
+                        .on_hover_text("how long events without an end are drawn");
+                    if input.changed() {
+                        self.open_duration = duration * nanos;
+                        open_duration_changed = true;
+                    }
+                }
+
+                ui.checkbox(&mut self.show_orphans, "Orphan ends")
+                    .on_hover_text("show events without a start, beginning at the earliest start");
+
+                ui.with_layout(Layout::right_to_left(eframe::emath::Align::Max), |ui| {
+                    ui.hyperlink_to(
+                        "github.com/HSMF/jfon-viewer",
+                        "https://github.com/HSMF/jfon-viewer",
+                    )
+                });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Time range: ");
+                let offset = self.time_offset(&events);
+                optional_value(ui, "from", &mut self.from, 0.0, offset);
+                optional_value(ui, "to", &mut self.to, events.end, offset);
+                ui.separator();
+                ui.label("Duration: ");
+                optional_duration(ui, "at least", &mut self.min_duration, 0.0, self.unit);
+                optional_duration(ui, "at most", &mut self.max_duration, events.end, self.unit);
+                ui.separator();
+                ui.checkbox(&mut self.absolute, "absolute time");
+                ui.label("unit: ");
+                ComboBox::from_id_source("unit")
+                    .selected_text(self.unit.to_string())
+                    .show_ui(ui, |ui| {
+                        for unit in TimeUnit::ALL {
+                            ui.selectable_value(&mut self.unit, unit, unit.to_string());
+                        }
+                    });
+                ui.add(egui::DragValue::new(&mut self.decimals).clamp_range(0..=12))
+                    .on_hover_text(
+                        "how many digits after the point times are shown and exported with",
+                    );
+                ui.label("colors: ");
+                ComboBox::from_id_source("palette")
+                    .selected_text(self.palette.to_string())
+                    .show_ui(ui, |ui| {
+                        for palette in Palette::ALL {
+                            ui.selectable_value(&mut self.palette, palette, palette.to_string());
+                        }
+                    });
+            });
+
+            #[cfg(not(target_arch = "wasm32"))]
+            self.show_too_large(ui);
+            let has_errors = {
+                let errors = self.errors.lock();
+                if !errors.is_empty() {
+                    show_errors(&errors, events.unlisted, ui);
+                }
+                !errors.is_empty()
+            };
+
+            self.plot_rect = None;
+            if events.events.is_empty() {
+                if !events.sources.is_empty() {
+                    ui.label(format!(
+                        "Loaded 0 events from {} ({} lines skipped)",
+                        events.sources.join(", "),
+                        events.skipped
+                    ));
+                } else if !has_errors {
+                    ui.label("Load some data to get started");
+                }
+            } else {
+                let response = match self.view {
+                    View::Timeline => self.show_timeline(ui, &events),
+                    View::Histogram => self.show_histogram(ui, &events),
+                    View::BoxPlot => self.show_box_plot(ui, &events),
+                };
+
+                let ppp = ctx.pixels_per_point();
+                let rect = response.rect;
+                self.plot_rect = Some(egui::Rect::from_min_max(
+                    (rect.min.to_vec2() * ppp).to_pos2(),
+                    (rect.max.to_vec2() * ppp).to_pos2(),
+                ));
+            }
+        });
+
+        drop(events);
+        if open_duration_changed {
+            Arc::make_mut(&mut self.events.lock()).set_open_duration(self.open_duration);
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+        #[cfg(not(target_arch = "wasm32"))]
+        eframe::set_value(storage, RECENT_FILES_KEY, &*self.recent.lock());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn post_rendering(&mut self, window_size_px: [u32; 2], frame: &eframe::Frame) {
+        use eframe::glow::{self, HasContext};
+
+        if !std::mem::take(&mut self.export_png) {
+            return;
+        }
+        let (Some(gl), Some(rect)) = (frame.gl(), self.plot_rect) else {
+            return;
+        };
+
+        let [window_width, window_height] = window_size_px;
+        let rect = rect.intersect(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(window_width as f32, window_height as f32),
+        ));
+        let x = rect.min.x.round() as u32;
+        let width = (rect.width().round() as u32).min(window_width - x);
+        let height = rect.height().round() as u32;
+        // opengl counts rows from the bottom of the window
+        let y = window_height.saturating_sub(rect.max.y.round() as u32);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        unsafe {
+            gl.read_pixels(
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut pixels),
+            );
+        }
+        let row = width as usize * 4;
+        let flipped: Vec<u8> = pixels.chunks_exact(row).rev().flatten().copied().collect();
+
+        match encode_png(width, height, &flipped) {
+            Ok(png) => save_file("timeline.png", "png", png, Arc::clone(&self.errors)),
+            Err(e) => {
+                let e = std::io::Error::other(e);
+                self.errors.lock().push(Error::IoError(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &str) -> Events {
+        let (events, errors) = Events::read(data, ReadOptions::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        events
+    }
+
+    #[test]
+    fn union_of_first_seen_rows_keeps_labels() {
+        // the compact format: label, id, start and end
+        let events = read("a 1 0 1\nb 1 0 1\nc 1 0 10\nc 2 5 20\nc 3 30 40\n");
+        assert_eq!(events.labels, ["a", "b", "c"]);
+        let shown: Vec<_> = events
+            .events
+            .iter()
+            .filter(|ev| ev.kind == "c")
+            .map(|ev| (ev, ev.span.end()))
+            .collect();
+        let rows = first_seen_rows(&[2, 2, 2], &[]);
+        assert_eq!(rows, [0, 0, 0]);
+        let union = union_spans(&shown, &rows);
+        assert_eq!(union, [("c", 0, 0.0, 20.0, 2), ("c", 0, 30.0, 40.0, 1)]);
+    }
+
+    #[test]
+    fn gaps_merge_within_rows() {
+        let events = read("a 1 0 10 thread=1\na 2 12 20 thread=2\na 3 15 30 thread=1\n");
+        let shown: Vec<_> = events.events.iter().map(|ev| (ev, ev.span.end())).collect();
+        // by thread, the first and last are joined across the one in between
+        assert_eq!(
+            merge_gaps(&shown, 5.0, |ev| ev.thread.unwrap_or(ev.id)),
+            [(0, 30.0, 2), (1, 20.0, 1)]
+        );
+        // by label, all three are
+        assert_eq!(merge_gaps(&shown, 5.0, |_| 0), [(0, 30.0, 3)]);
+    }
+
+    #[test]
+    fn palette_colors_are_stable() {
+        // FNV-1a of "fetch" is 9 modulo 10 and 163 modulo 1024, of "" the offset basis
+        assert_eq!(Palette::Categorical.color("fetch"), Palette::CATEGORICAL[9]);
+        assert_eq!(Palette::Categorical.color(""), Palette::CATEGORICAL[7]);
+        assert_eq!(
+            Palette::Hues.color("fetch"),
+            Hsva::new(163.0 / 1024.0, 0.85, 0.5, 1.0).into()
+        );
+    }
+
+    #[test]
+    fn visible_events_by_id() {
+        let events = read("a 1 0 1\nb 2 0 1\na 2 1 3\n");
+        let mut analyzer = Analyzer::default();
+        let visible = |analyzer: &Analyzer| {
+            let mut visible: Vec<_> = analyzer
+                .visible_events(&events)
+                .map(|ev| format!("{} {}", ev.kind, ev.id))
+                .collect();
+            visible.sort();
+            visible
+        };
+        for (id_filter, expected) in [
+            ("", &["a 1", "a 2", "b 2"][..]),
+            (" 2 ", &["a 2", "b 2"]),
+            ("7", &[]),
+            ("x", &["a 1", "a 2", "b 2"]),
+        ] {
+            analyzer.id_filter = id_filter.to_owned();
+            analyzer.parse_id_filter();
+            assert_eq!(visible(&analyzer), expected, "{id_filter:?}");
+        }
+
+        // along with the label
+        analyzer.id_filter = "2".to_owned();
+        analyzer.parse_id_filter();
+        analyzer.view_by = ViewBy::Label("a".to_owned());
+        assert_eq!(visible(&analyzer), ["a 2"]);
+    }
+
+    #[test]
+    fn stats_of_durations() {
+        assert_eq!(Stats::new(Vec::new()), None);
+        let stats = Stats::new((1..=10).rev().map(f64::from).collect()).unwrap();
+        assert_eq!(
+            stats,
+            Stats {
+                count: 10,
+                min: 1.0,
+                max: 10.0,
+                mean: 5.5,
+                median: 5.5,
+                q1: 3.0,
+                q3: 8.0,
+                p90: 9.0,
+                p95: 10.0,
+                p99: 10.0,
+            }
+        );
+        // the middle one for an odd count
+        assert_eq!(Stats::new(vec![3.0, 1.0, 8.0]).unwrap().median, 3.0);
+    }
+
+    #[test]
+    fn concurrency_of_zero_length_spans() {
+        assert_eq!(
+            concurrency([(0.0, 5.0), (5.0, 5.0), (2.0, 2.0)].into_iter()),
+            [(0.0, 1), (5.0, 0)]
+        );
+        assert!(concurrency([(5.0, 5.0)].into_iter()).is_empty());
+
+        // which the stats panel does for every file
+        let stats = Analyzer::default().compute_stats(&Arc::new(read("a:1,start,5\na:1,end,5\n")));
+        assert_eq!(stats.total.count, 1);
+    }
+
+    #[test]
+    fn label_stats_leave_out_open_events() {
+        let (events, _) = Events::read(
+            "a:1,start,0\na:1,end,4\na:2,start,2\na:2,end,4\na:3,start,10\na:4,end,3\n",
+            ReadOptions::default(),
+        );
+        let visible: Vec<_> = events.events.iter().collect();
+        let stats = LabelStats::new("a".to_owned(), &visible);
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.open, 1);
+        let durations = stats.durations.unwrap();
+        assert_eq!(
+            (durations.count, durations.min, durations.max),
+            (2, 2.0, 4.0)
+        );
+        assert_eq!(durations.mean, 3.0);
+    }
+
+    #[test]
+    fn csv_leaves_out_placeholder_times() {
+        let (events, _) = Events::read(
+            "a:1,start,10\na:1,end,15\nb:1,start,20\nc:1,end,30\n",
+            ReadOptions::default(),
+        );
+        let csv = write_csv(&events.events, |_| events.offset, 3).unwrap();
+        let mut rows: Vec<_> = String::from_utf8(csv)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        rows[1..].sort();
+        assert_eq!(
+            rows,
+            [
+                "kind,id,start,duration,end",
+                "a,1,10,5,15",
+                "b,1,20,,",
+                "c,1,,,30"
+            ]
+        );
+    }
 }